@@ -321,6 +321,16 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_gen_token_decodes() {
+        use std::rand::{task_rng, Rng};
+
+        for byte_len in range(0u, 50) {
+            let token = task_rng().gen_token(byte_len);
+            assert_eq!(token.from_base64().unwrap().len(), byte_len);
+        }
+    }
+
     #[bench]
     pub fn bench_to_base64(bh: & mut BenchHarness) {
         let s = "イロハニホヘト チリヌルヲ ワカヨタレソ ツネナラム \
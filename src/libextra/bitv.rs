@@ -16,6 +16,7 @@ use std::iter::RandomAccessIterator;
 use std::iter::{Invert, Enumerate, Repeat, Map, Zip};
 use std::num;
 use std::ops;
+use std::rand::Rng;
 use std::uint;
 use std::vec;
 
@@ -550,6 +551,41 @@ pub fn from_fn(len: uint, f: &fn(index: uint) -> bool) -> Bitv {
     bitv
 }
 
+/// Extension methods for filling a `Bitv` from a random number generator.
+pub trait BitvRng {
+    /**
+     * Sets each bit of `bv` independently with probability `density`,
+     * overwriting its previous contents.
+     *
+     * A density of exactly 0.5 fills whole storage words at a time rather
+     * than drawing a value per bit. Fails if `density` is outside `[0, 1]`.
+     */
+    fn fill_bitv(&mut self, bv: &mut Bitv, density: f64);
+}
+
+impl<R: Rng> BitvRng for R {
+    fn fill_bitv(&mut self, bv: &mut Bitv, density: f64) {
+        assert!(density >= 0.0 && density <= 1.0,
+                "BitvRng.fill_bitv called with density outside [0, 1]");
+        if density == 0.5 {
+            // Every bit is a fair coin flip, so a random word is exactly
+            // what we want. Bits past `nbits` are undefined anyway.
+            match bv.rep {
+                Small(ref mut s) => s.bits = self.gen(),
+                Big(ref mut s) => {
+                    for w in s.storage.mut_iter() {
+                        *w = self.gen();
+                    }
+                }
+            }
+        } else {
+            for i in range(0u, bv.nbits) {
+                bv.set(i, self.gen::<f64>() < density);
+            }
+        }
+    }
+}
+
 impl ops::Index<uint,bool> for Bitv {
     fn index(&self, i: &uint) -> bool {
         self.get(*i)
@@ -1523,6 +1559,32 @@ mod tests {
         assert!(b.contains(&1000));
     }
 
+    #[test]
+    fn test_fill_bitv() {
+        let mut r = rng();
+        let mut bitv = Bitv::new(10000, false);
+        r.fill_bitv(&mut bitv, 0.3);
+        let ones = bitv.iter().count(|b| b);
+        assert!(ones > 2700 && ones < 3300);
+
+        r.fill_bitv(&mut bitv, 0.5);
+        let ones = bitv.iter().count(|b| b);
+        assert!(ones > 4700 && ones < 5300);
+
+        r.fill_bitv(&mut bitv, 0.0);
+        assert!(bitv.is_false());
+        r.fill_bitv(&mut bitv, 1.0);
+        assert!(bitv.is_true());
+    }
+
+    #[test]
+    #[should_fail]
+    fn test_fill_bitv_bad_density() {
+        let mut r = rng();
+        let mut bitv = Bitv::new(10, false);
+        r.fill_bitv(&mut bitv, 1.5);
+    }
+
     fn rng() -> rand::IsaacRng {
         let seed = [1, 2, 3, 4, 5, 6, 7, 8, 9, 0];
         rand::IsaacRng::new_seeded(seed)
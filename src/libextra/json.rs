@@ -23,8 +23,10 @@ use std::hashmap::HashMap;
 use std::io::WriterUtil;
 use std::io;
 use std::num;
+use std::rand::Rng;
 use std::str;
 use std::to_str;
+use std::vec;
 
 use serialize::Encodable;
 use serialize;
@@ -1303,6 +1305,48 @@ impl<A:ToJson> ToJson for Option<A> {
     }
 }
 
+/// Extension methods for generating random JSON values, for fuzzing JSON
+/// handlers.
+pub trait JsonRng {
+    /**
+     * Returns a random JSON value nested at most `max_depth` levels deep.
+     *
+     * Nulls, booleans, numbers and strings have depth 0; a list or object
+     * is one level deeper than its deepest member. Lists and objects hold
+     * at most 4 members and are only generated while depth remains, so
+     * `gen_json(0)` always returns a scalar.
+     */
+    fn gen_json(&mut self, max_depth: uint) -> Json;
+}
+
+impl<R: Rng> JsonRng for R {
+    fn gen_json(&mut self, max_depth: uint) -> Json {
+        static MAX_MEMBERS: uint = 5;
+        let kinds = if max_depth == 0 { 4 } else { 6 };
+        match self.gen_integer_range(0u, kinds) {
+            0 => Null,
+            1 => Boolean(self.gen()),
+            2 => Number(self.gen_integer_range(-1000000i, 1000000) as f64 / 100.0),
+            3 => {
+                let len = self.gen_integer_range(0u, 8);
+                String(self.gen_ascii_str(len))
+            }
+            4 => {
+                let len = self.gen_integer_range(0u, MAX_MEMBERS);
+                List(vec::from_fn(len, |_| self.gen_json(max_depth - 1)))
+            }
+            _ => {
+                let mut obj = ~TreeMap::new();
+                for _ in range(0, self.gen_integer_range(0u, MAX_MEMBERS)) {
+                    let len = self.gen_integer_range(0u, 8);
+                    obj.insert(self.gen_ascii_str(len), self.gen_json(max_depth - 1));
+                }
+                Object(obj)
+            }
+        }
+    }
+}
+
 impl to_str::ToStr for Json {
     /// Encodes a json value into a string
     fn to_str(&self) -> ~str {
@@ -1944,4 +1988,33 @@ mod tests {
                 col: 8u,
                 msg: @~"EOF while parsing object"}));
     }
+
+    #[test]
+    fn test_gen_json() {
+        use std::num;
+        use std::rand;
+
+        fn depth(json: &Json) -> uint {
+            match *json {
+                List(ref list) => 1 + list.iter().fold(0, |d, j| num::max(d, depth(j))),
+                Object(ref obj) => 1 + obj.iter().fold(0, |d, (_, j)| num::max(d, depth(j))),
+                _ => 0
+            }
+        }
+
+        let mut rng = rand::rng();
+        for max_depth in range(0u, 4) {
+            let mut deepest = 0;
+            for _ in range(0, 200) {
+                let json = rng.gen_json(max_depth);
+                let d = depth(&json);
+                assert!(d <= max_depth);
+                deepest = num::max(deepest, d);
+                assert!(from_str(json.to_str()).is_ok());
+            }
+            if max_depth > 0 {
+                assert!(deepest > 0);
+            }
+        }
+    }
 }
@@ -34,6 +34,7 @@ use std::io::{ReaderUtil};
 use std::io;
 use std::option::{Option, Some, None};
 use std::to_str::ToStr;
+use std::uint;
 
 /// An identifier in the pre-release or build metadata. If the identifier can
 /// be parsed as a decimal value, it will be represented with `Numeric`.
@@ -246,6 +247,241 @@ pub fn parse(s: &str) -> Option<Version> {
     }
 }
 
+/// One end of the range of versions accepted by a `VersionReq`.
+#[deriving(Clone, Eq)]
+struct Bound {
+    version: Version,
+    inclusive: bool
+}
+
+/// A requirement on a version, such as `>=1.0,<2.0`, for dependency
+/// resolution.
+///
+/// A requirement is a comma-separated list of comparisons, all of which must
+/// hold. Each comparison is one of `<`, `<=`, `>`, `>=` or `=` followed by a
+/// version; a version with no operator means `=`. Missing minor and patch
+/// numbers are taken to be 0, so `>=1.0` is `>=1.0.0`. A version ending in
+/// `*`, with no operator or with `=`, accepts every version it could be
+/// completed to: `1.2.*` is `>=1.2.0,<1.3.0-0`, `1.*` is `>=1.0.0,<2.0.0-0`
+/// and a bare `*` accepts everything. The upper bound `-0` is the lowest
+/// pre-release, so pre-releases of the next version, such as `2.0.0-alpha`,
+/// don't match `1.*`. A wildcard on the largest possible number has no
+/// upper bound. The accepted versions always form a single range, which is
+/// what lets two requirements be combined with `intersect`.
+#[deriving(Clone, Eq)]
+pub struct VersionReq {
+    priv lower: Option<Bound>,
+    priv upper: Option<Bound>
+}
+
+impl VersionReq {
+    /// Returns the requirement that accepts every version.
+    pub fn any() -> VersionReq {
+        VersionReq { lower: None, upper: None }
+    }
+
+    /// Returns true if `version` satisfies the requirement.
+    pub fn matches(&self, version: &Version) -> bool {
+        let above = match self.lower {
+            None => true,
+            Some(ref b) => if b.inclusive { *version >= b.version } else { *version > b.version }
+        };
+        let below = match self.upper {
+            None => true,
+            Some(ref b) => if b.inclusive { *version <= b.version } else { *version < b.version }
+        };
+        above && below
+    }
+
+    /// Returns the requirement satisfied by exactly the versions that
+    /// satisfy both `self` and `other`, or None if no version can satisfy
+    /// both.
+    pub fn intersect(&self, other: &VersionReq) -> Option<VersionReq> {
+        // Of two bounds on the same side, keep the tighter one: `later`
+        // says whether the larger version is tighter.
+        fn tighter(a: &Option<Bound>, b: &Option<Bound>, later: bool) -> Option<Bound> {
+            match (a, b) {
+                (&None, _) => b.clone(),
+                (_, &None) => a.clone(),
+                (&Some(ref x), &Some(ref y)) => {
+                    if x.version == y.version {
+                        Some(Bound { version: x.version.clone(),
+                                     inclusive: x.inclusive && y.inclusive })
+                    } else if (x.version > y.version) == later {
+                        Some(x.clone())
+                    } else {
+                        Some(y.clone())
+                    }
+                }
+            }
+        }
+
+        let req = VersionReq {
+            lower: tighter(&self.lower, &other.lower, true),
+            upper: tighter(&self.upper, &other.upper, false)
+        };
+        match (&req.lower, &req.upper) {
+            (&Some(ref l), &Some(ref u)) => {
+                if l.version > u.version ||
+                   (l.version == u.version && !(l.inclusive && u.inclusive)) {
+                    return None;
+                }
+            }
+            _ => ()
+        }
+        Some(req)
+    }
+}
+
+impl ToStr for VersionReq {
+    fn to_str(&self) -> ~str {
+        match (&self.lower, &self.upper) {
+            (&None, &None) => ~"*",
+            (&Some(ref l), &Some(ref u)) if l.inclusive && u.inclusive &&
+                                            l.version == u.version => {
+                format!("={}", l.version.to_str())
+            }
+            _ => {
+                let mut parts = ~[];
+                for b in self.lower.iter() {
+                    parts.push(format!("{}{}", if b.inclusive { ">=" } else { ">" },
+                                       b.version.to_str()));
+                }
+                for b in self.upper.iter() {
+                    parts.push(format!("{}{}", if b.inclusive { "<=" } else { "<" },
+                                       b.version.to_str()));
+                }
+                parts.connect(",")
+            }
+        }
+    }
+}
+
+// Parses a version that may leave out the minor and patch numbers, which
+// are then 0. Only a full version may have pre-release or build parts.
+fn parse_partial(s: &str) -> Option<Version> {
+    let parts: ~[&str] = s.split_iter('.').collect();
+    if parts.len() >= 3 {
+        return parse(s);
+    }
+    let mut nums = ~[];
+    for part in parts.iter() {
+        if part.is_empty() || !part.iter().all(char::is_digit) {
+            return None;
+        }
+        match from_str::<uint>(*part) {
+            Some(n) => nums.push(n),
+            None => return None
+        }
+    }
+    Some(Version {
+        major: nums[0],
+        minor: if nums.len() > 1 { nums[1] } else { 0 },
+        patch: 0,
+        pre: ~[],
+        build: ~[],
+    })
+}
+
+// Parses a wildcard version such as `1.2.*`, `1.*` or `*` into the range
+// of versions it stands for.
+fn parse_wildcard(s: &str) -> Option<VersionReq> {
+    let parts: ~[&str] = s.split_iter('.').collect();
+    if parts.len() > 3 || *parts.last() != "*" {
+        return None;
+    }
+    let mut nums = ~[];
+    for part in parts.slice_to(parts.len() - 1).iter() {
+        if part.is_empty() || !part.iter().all(char::is_digit) {
+            return None;
+        }
+        match from_str::<uint>(*part) {
+            Some(n) => nums.push(n),
+            None => return None
+        }
+    }
+    let version = |major, minor, pre| Version {
+        major: major,
+        minor: minor,
+        patch: 0,
+        pre: pre,
+        build: ~[],
+    };
+    // The upper bound is the lowest pre-release of the next version, or
+    // none at all if that version can't be represented.
+    let (lower, upper) = match nums.len() {
+        0 => return Some(VersionReq::any()),
+        1 => (version(nums[0], 0, ~[]),
+              if nums[0] == uint::max_value {
+                  None
+              } else {
+                  Some(version(nums[0] + 1, 0, ~[Numeric(0)]))
+              }),
+        _ => (version(nums[0], nums[1], ~[]),
+              if nums[1] < uint::max_value {
+                  Some(version(nums[0], nums[1] + 1, ~[Numeric(0)]))
+              } else if nums[0] < uint::max_value {
+                  Some(version(nums[0] + 1, 0, ~[Numeric(0)]))
+              } else {
+                  None
+              })
+    };
+    Some(VersionReq {
+        lower: Some(Bound { version: lower, inclusive: true }),
+        upper: upper.map(|v| Bound { version: v.clone(), inclusive: false })
+    })
+}
+
+/// Parse a string such as `>=1.0,<2.0` into a version requirement.
+pub fn parse_req(s: &str) -> Option<VersionReq> {
+    if !s.is_ascii() {
+        return None;
+    }
+    let mut req = VersionReq::any();
+    for pred in s.split_iter(',') {
+        let pred = pred.trim();
+        let (op, rest) = if pred.starts_with("<=") || pred.starts_with(">=") {
+            (pred.slice_to(2), pred.slice_from(2))
+        } else if pred.starts_with("<") || pred.starts_with(">") || pred.starts_with("=") {
+            (pred.slice_to(1), pred.slice_from(1))
+        } else {
+            ("=", pred)
+        };
+        let rest = rest.trim();
+        if rest.ends_with("*") {
+            if op != "=" {
+                return None;
+            }
+            let pred = match parse_wildcard(rest) {
+                Some(p) => p,
+                None => return None
+            };
+            req = match req.intersect(&pred) {
+                Some(r) => r,
+                None => return None
+            };
+            continue;
+        }
+        let version = match parse_partial(rest) {
+            Some(v) => v,
+            None => return None
+        };
+        let bound = |inclusive| Some(Bound { version: version.clone(), inclusive: inclusive });
+        let pred = match op {
+            "<" => VersionReq { lower: None, upper: bound(false) },
+            "<=" => VersionReq { lower: None, upper: bound(true) },
+            ">" => VersionReq { lower: bound(false), upper: None },
+            ">=" => VersionReq { lower: bound(true), upper: None },
+            _ => VersionReq { lower: bound(true), upper: bound(true) }
+        };
+        req = match req.intersect(&pred) {
+            Some(r) => r,
+            None => return None
+        };
+    }
+    Some(req)
+}
+
 #[test]
 fn test_parse() {
     assert_eq!(parse(""), None);
@@ -405,3 +641,112 @@ fn test_spec_order() {
         i += 1;
     }
 }
+
+#[test]
+fn test_parse_req() {
+    let req = parse_req(">=1.0, <2.0").unwrap();
+    assert_eq!(req.to_str(), ~">=1.0.0,<2.0.0");
+    assert_eq!(parse_req("1.2.3").unwrap().to_str(), ~"=1.2.3");
+    assert_eq!(parse_req("=1.2.3-alpha").unwrap().to_str(), ~"=1.2.3-alpha");
+    assert_eq!(parse_req(">1").unwrap().to_str(), ~">1.0.0");
+    assert_eq!(parse_req(req.to_str()), Some(req));
+
+    assert_eq!(parse_req(""), None);
+    assert_eq!(parse_req(">="), None);
+    assert_eq!(parse_req(">=1.a"), None);
+    assert_eq!(parse_req("1..2"), None);
+    assert_eq!(parse_req("~1.2"), None);
+    // A requirement no version can satisfy is rejected.
+    assert_eq!(parse_req(">2.0,<1.0"), None);
+}
+
+#[test]
+fn test_req_matches() {
+    let req = parse_req(">=1.0,<2.0").unwrap();
+    assert!(req.matches(&parse("1.0.0").unwrap()));
+    assert!(req.matches(&parse("1.9.9").unwrap()));
+    assert!(!req.matches(&parse("2.0.0").unwrap()));
+    assert!(!req.matches(&parse("0.9.9").unwrap()));
+
+    let req = parse_req("=1.2.3").unwrap();
+    assert!(req.matches(&parse("1.2.3").unwrap()));
+    assert!(!req.matches(&parse("1.2.4").unwrap()));
+
+    assert!(VersionReq::any().matches(&parse("0.0.1").unwrap()));
+}
+
+#[test]
+fn test_req_intersect() {
+    let at_least_1 = parse_req(">=1.0").unwrap();
+    let below_2 = parse_req("<2.0").unwrap();
+    assert_eq!(at_least_1.intersect(&below_2), parse_req(">=1.0,<2.0"));
+    assert_eq!(below_2.intersect(&at_least_1), parse_req(">=1.0,<2.0"));
+
+    let below_1 = parse_req("<1.0").unwrap();
+    let at_least_2 = parse_req(">=2.0").unwrap();
+    assert_eq!(below_1.intersect(&at_least_2), None);
+
+    // The tighter of two bounds on the same side wins.
+    assert_eq!(parse_req(">=1.0,<3.0").unwrap().intersect(&parse_req(">1.5,<2.0").unwrap()),
+               parse_req(">1.5,<2.0"));
+    assert_eq!(parse_req(">=1.0").unwrap().intersect(&parse_req(">1.0").unwrap()),
+               parse_req(">1.0"));
+
+    // Ranges that only touch at an end meet only if both ends include it.
+    assert_eq!(parse_req("<=1.0").unwrap().intersect(&at_least_1), parse_req("=1.0"));
+    assert_eq!(below_1.intersect(&at_least_1), None);
+
+    assert_eq!(VersionReq::any().intersect(&below_2), Some(below_2.clone()));
+}
+
+#[test]
+fn test_req_wildcard() {
+    let matches = |req: &str, v: &str| parse_req(req).unwrap().matches(&parse(v).unwrap());
+
+    assert!(matches("1.2.*", "1.2.0"));
+    assert!(matches("1.2.*", "1.2.99"));
+    assert!(!matches("1.2.*", "1.3.0"));
+    assert!(!matches("1.2.*", "1.1.9"));
+    assert!(!matches("1.2.*", "2.2.0"));
+
+    assert!(matches("1.*", "1.0.0"));
+    assert!(matches("1.*", "1.99.3"));
+    assert!(!matches("1.*", "2.0.0"));
+    assert!(!matches("1.*", "0.9.9"));
+
+    assert!(matches("*", "0.0.1"));
+    assert!(matches("*", "99.0.0"));
+    assert!(matches("=1.2.*", "1.2.5"));
+
+    // Pre-releases of the next version are excluded, those of the first
+    // one are below the range anyway.
+    assert!(!matches("1.*", "2.0.0-alpha"));
+    assert!(!matches("1.*", "2.0.0-0"));
+    assert!(!matches("1.2.*", "1.3.0-rc.1"));
+    assert!(matches("1.2.*", "1.2.5-rc.1"));
+    assert!(!matches("1.2.*", "1.2.0-rc.1"));
+
+    assert_eq!(parse_req("1.2.*"), parse_req(">=1.2.0,<1.3.0-0"));
+    assert_eq!(parse_req("1.*"), parse_req(">=1.0.0,<2.0.0-0"));
+    assert_eq!(parse_req("*"), Some(VersionReq::any()));
+    assert_eq!(parse_req("1.*,<1.5"), parse_req(">=1.0,<1.5"));
+
+    assert_eq!(parse_req(">=1.*"), None);
+    assert_eq!(parse_req("1.*.3"), None);
+    assert_eq!(parse_req("1.2.3.*"), None);
+    assert_eq!(parse_req("1.2*"), None);
+    assert_eq!(parse_req("1.*,2.*"), None);
+
+    // Numbers at the top of the range don't wrap around to an upper bound
+    // of 0.
+    let max = uint::max_value;
+    let max_major = format!("{}.*", max);
+    assert!(matches(max_major, format!("{}.7.0", max)));
+    assert_eq!(parse_req(max_major), parse_req(format!(">={}.0.0", max)));
+    let max_minor = format!("1.{}.*", max);
+    assert!(matches(max_minor, format!("1.{}.3", max)));
+    assert!(!matches(max_minor, "2.0.0"));
+    assert_eq!(parse_req(max_minor), parse_req(format!(">=1.{}.0,<2.0.0-0", max)));
+    assert_eq!(parse_req(format!("{}.{}.*", max, max)),
+               parse_req(format!(">={}.{}.0", max, max)));
+}
@@ -10,9 +10,17 @@
 
 #[allow(missing_doc)];
 
+use std::char;
+use std::i64;
 use std::io;
+use std::libc::{c_char, c_int, c_long};
 use std::num;
+use std::ptr;
+use std::rand::Rng;
+use std::rand::distributions::Exp1;
 use std::str;
+use std::vec;
+use serialize::{Decodable, Decoder, Encodable, Encoder};
 
 static NSEC_PER_SEC: i32 = 1_000_000_000_i32;
 
@@ -48,6 +56,126 @@ impl Timespec {
         assert!(nsec >= 0 && nsec < NSEC_PER_SEC);
         Timespec { sec: sec, nsec: nsec }
     }
+
+    /**
+     * Creates a `Timespec` from any number of nanoseconds, carrying whole
+     * seconds into (or borrowing them from) `sec` so that `nsec` ends up in
+     * `[0, NSEC_PER_SEC)`. Unlike `new` this never fails, which suits
+     * arithmetic whose intermediate results overflow the nanosecond field.
+     */
+    pub fn new_normalized(sec: i64, nsec: i64) -> Timespec {
+        let mut carry = nsec / NSEC_PER_SEC as i64;
+        let mut nsec = nsec % NSEC_PER_SEC as i64;
+        if nsec < 0 {
+            carry -= 1;
+            nsec += NSEC_PER_SEC as i64;
+        }
+        Timespec::new(sec + carry, nsec as i32)
+    }
+
+    /**
+     * Returns true if this time and `other` are at most `tolerance_nanos`
+     * nanoseconds apart, in either direction. The difference is computed
+     * with checked arithmetic, so times at opposite ends of the range
+     * compare as far apart rather than overflowing. A negative tolerance
+     * never matches.
+     */
+    pub fn approx_eq(&self, other: &Timespec, tolerance_nanos: i64) -> bool {
+        let (later, earlier) = if *self >= *other { (self, other) } else { (other, self) };
+        let diff = later.sec.checked_sub(&earlier.sec)
+            .and_then(|sec| sec.checked_mul(&(NSEC_PER_SEC as i64)))
+            .and_then(|ns| ns.checked_add(&((later.nsec - earlier.nsec) as i64)));
+        match diff {
+            Some(diff) => diff <= tolerance_nanos,
+            None => false
+        }
+    }
+
+    /**
+     * Returns this time in UTC formatted according to RFC 3339, e.g.
+     * "2009-02-13T23:31:30Z". Equivalent to `at_utc(*self).rfc3339()`,
+     * but converts without calling into the C runtime.
+     */
+    pub fn to_iso8601_utc(&self) -> ~str {
+        at_utc_pure(*self).rfc3339()
+    }
+
+    /**
+     * Parses an RFC 3339 time such as "2009-02-13T23:31:30Z" or
+     * "2009-02-13T15:31:30.5-08:00". Fractional seconds are optional, and
+     * the zone must be either "Z" or a `+HH:MM` / `-HH:MM` offset, which is
+     * applied when converting to seconds since the epoch. This accepts the
+     * output of `to_iso8601_utc` and `Tm::rfc3339`.
+     */
+    pub fn from_iso8601(s: &str) -> Result<Timespec, ~str> {
+        static DATE_TIME_LEN: uint = 19; // "2009-02-13T23:31:30"
+
+        let s = s.trim();
+        if !s.is_ascii() || s.len() <= DATE_TIME_LEN {
+            return Err(format!("Invalid RFC 3339 time: '{}'", s));
+        }
+        let (date_time, offset) = if s.ends_with("Z") || s.ends_with("z") {
+            (s.slice_to(s.len() - 1), 0)
+        } else {
+            let zone = s.len() - 6;
+            let (sign, hours, minutes) =
+                match strptime(s.slice_from(zone + 1), "%H:%M") {
+                    Ok(tm) => (s[zone], tm.tm_hour as i64, tm.tm_min as i64),
+                    Err(e) => return Err(e)
+                };
+            let offset = hours * 3600 + minutes * 60;
+            match sign as char {
+                '+' => (s.slice_to(zone), offset),
+                '-' => (s.slice_to(zone), -offset),
+                _ => return Err(format!("Invalid zone offset: '{}'",
+                                        s.slice_from(zone)))
+            }
+        };
+
+        let format = if date_time.len() == DATE_TIME_LEN {
+            "%Y-%m-%dT%H:%M:%S"
+        } else {
+            "%Y-%m-%dT%H:%M:%S.%f"
+        };
+        match strptime(date_time, format) {
+            Ok(tm) => {
+                let days = days_from_civil(tm.tm_year as i64 + 1900,
+                                           tm.tm_mon as i64,
+                                           tm.tm_mday as i64);
+                let secs = tm.tm_hour as i64 * 3600 + tm.tm_min as i64 * 60 +
+                    tm.tm_sec as i64;
+                Ok(Timespec::new(days * 86400 + secs - offset, tm.tm_nsec))
+            }
+            Err(e) => Err(e)
+        }
+    }
+
+    /**
+     * Rounds to the nearest multiple of `step_ns` nanoseconds since the
+     * epoch, rounding halfway cases up (towards the later time). The step
+     * need not divide a minute or a day evenly. Fails if `step_ns` is 0.
+     *
+     * The instant is handled as a count of nanoseconds, so both it and the
+     * rounded result must lie within about 292 years of the epoch; this
+     * fails if either does not.
+     */
+    pub fn round_to(&self, step_ns: u64) -> Timespec {
+        assert!(step_ns > 0 && step_ns <= i64::max_value as u64,
+                "Timespec::round_to called with an invalid step");
+        let step = step_ns as i64;
+        let total = self.sec.checked_mul(&(NSEC_PER_SEC as i64))
+            .and_then(|ns| ns.checked_add(&(self.nsec as i64)))
+            .expect("Timespec::round_to called on a time too far from the epoch");
+        let mut rem = total % step;
+        if rem < 0 {
+            rem += step;
+        }
+        let up = rem >= step - rem;
+        let rounded = total.checked_sub(&rem)
+            .and_then(|ns| if up { ns.checked_add(&step) } else { Some(ns) })
+            .expect("Timespec::round_to: rounded time too far from the epoch");
+        Timespec::new_normalized(0, rounded)
+    }
 }
 
 impl Ord for Timespec {
@@ -57,6 +185,142 @@ impl Ord for Timespec {
     }
 }
 
+/**
+ * The time scale a `Timespec` counts in. `UTC` is what `get_time` returns:
+ * like all POSIX times it skips leap seconds, so it is not continuous across
+ * them. `TAI` counts every SI second, so differences between `TAI` times
+ * are true elapsed durations.
+ */
+#[deriving(Clone, Eq)]
+pub enum TimeScale {
+    UTC,
+    TAI,
+}
+
+/**
+ * The UTC times (as POSIX seconds) from which TAI has been each number of
+ * seconds ahead of UTC, one row per leap second. This must be extended
+ * whenever the IERS announces a new leap second; the last entry is the one
+ * inserted at the end of 2016.
+ */
+static LEAP_SECONDS: [(i64, i32), ..28] = [
+    (63072000, 10),     // 1972-01-01
+    (78796800, 11),     // 1972-07-01
+    (94694400, 12),     // 1973-01-01
+    (126230400, 13),    // 1974-01-01
+    (157766400, 14),    // 1975-01-01
+    (189302400, 15),    // 1976-01-01
+    (220924800, 16),    // 1977-01-01
+    (252460800, 17),    // 1978-01-01
+    (283996800, 18),    // 1979-01-01
+    (315532800, 19),    // 1980-01-01
+    (362793600, 20),    // 1981-07-01
+    (394329600, 21),    // 1982-07-01
+    (425865600, 22),    // 1983-07-01
+    (489024000, 23),    // 1985-07-01
+    (567993600, 24),    // 1988-01-01
+    (631152000, 25),    // 1990-01-01
+    (662688000, 26),    // 1991-01-01
+    (709948800, 27),    // 1992-07-01
+    (741484800, 28),    // 1993-07-01
+    (773020800, 29),    // 1994-07-01
+    (820454400, 30),    // 1996-01-01
+    (867715200, 31),    // 1997-07-01
+    (915148800, 32),    // 1999-01-01
+    (1136073600, 33),   // 2006-01-01
+    (1230768000, 34),   // 2009-01-01
+    (1341100800, 35),   // 2012-07-01
+    (1435708800, 36),   // 2015-07-01
+    (1483228800, 37),   // 2017-01-01
+];
+
+/**
+ * Returns the number of seconds TAI is ahead of UTC at the UTC time `ts`,
+ * from a built-in table of leap seconds. Times after the last leap second
+ * in the table get its offset, so the table needs updating when a new one
+ * is announced. Before 1972, when UTC was not yet kept a whole number of
+ * seconds from TAI, this returns the initial offset of 10.
+ */
+pub fn utc_to_tai_offset(ts: &Timespec) -> i32 {
+    let (_, mut offset) = LEAP_SECONDS[0];
+    for &(start, secs) in LEAP_SECONDS.iter() {
+        if ts.sec < start {
+            break;
+        }
+        offset = secs;
+    }
+    offset
+}
+
+/**
+ * Converts `ts` from the time scale `from` to the time scale `to`. To find
+ * the true number of seconds between two UTC times, convert both to `TAI`
+ * and subtract. A UTC time cannot name a leap second itself, so converting
+ * a TAI time within one gives the UTC second after it.
+ */
+pub fn convert_time_scale(ts: &Timespec, from: TimeScale, to: TimeScale) -> Timespec {
+    match (from, to) {
+        (UTC, TAI) => Timespec::new(ts.sec + utc_to_tai_offset(ts) as i64, ts.nsec),
+        (TAI, UTC) => {
+            // The offset at a UTC time is close enough to find the UTC time
+            // to look up the exact offset at.
+            let guess = Timespec::new(ts.sec - utc_to_tai_offset(ts) as i64, ts.nsec);
+            Timespec::new(ts.sec - utc_to_tai_offset(&guess) as i64, ts.nsec)
+        }
+        _ => *ts
+    }
+}
+
+/**
+ * A time as a single count of nanoseconds since 1970-01-01T00:00:00Z.
+ *
+ * This is a compact alternative to `Timespec` for storage: it serializes as
+ * one `i64` rather than a two-field struct, and orders like the integer it
+ * wraps. The count only reaches about 292 years either side of the epoch
+ * (1677-09-21 to 2262-04-11), so converting a `Timespec` outside that range
+ * fails.
+ */
+#[deriving(Clone, DeepClone, Eq, Ord)]
+pub struct NanoTime(i64);
+
+impl NanoTime {
+    /**
+     * Converts a `Timespec` to a nanosecond count, or returns `None` if it
+     * lies too far from the epoch for the count to fit in an `i64`.
+     */
+    pub fn from_timespec(ts: Timespec) -> Option<NanoTime> {
+        // Borrow a second for negative times so that the multiplication
+        // does not overflow when the sum would not.
+        let (sec, nsec) = if ts.sec < 0 && ts.nsec > 0 {
+            (ts.sec + 1, ts.nsec as i64 - NSEC_PER_SEC as i64)
+        } else {
+            (ts.sec, ts.nsec as i64)
+        };
+        sec.checked_mul(&(NSEC_PER_SEC as i64))
+            .and_then(|ns| ns.checked_add(&nsec))
+            .map(|ns| NanoTime(*ns))
+    }
+
+    /// Converts the nanosecond count back to a `Timespec`.
+    pub fn to_timespec(&self) -> Timespec {
+        let NanoTime(ns) = *self;
+        Timespec::new_normalized(0, ns)
+    }
+}
+
+impl<S: Encoder> Encodable<S> for NanoTime {
+    fn encode(&self, s: &mut S) {
+        let NanoTime(ns) = *self;
+        s.emit_i64(ns)
+    }
+}
+
+impl<D: Decoder> Decodable<D> for NanoTime {
+    fn decode(d: &mut D) -> NanoTime {
+        NanoTime(d.read_i64())
+    }
+}
+
 /**
  * Returns the current time as a `timespec` containing the seconds and
  * nanoseconds since 1970-01-01T00:00:00Z.
@@ -114,12 +378,95 @@ pub struct Tm {
     tm_year: i32, // years since 1900
     tm_wday: i32, // days since Sunday ~[0-6]
     tm_yday: i32, // days since January 1 ~[0-365]
-    tm_isdst: i32, // Daylight Savings Time flag
+    tm_isdst: i32, // Daylight Savings Time flag: 1 on, 0 off, -1 unknown
     tm_gmtoff: i32, // offset from UTC in seconds
     tm_zone: ~str, // timezone abbreviation
     tm_nsec: i32, // nanoseconds
 }
 
+/**
+ * A time laid out like the platform's C `struct tm`, for passing to C
+ * functions such as `mktime`. The fields keep `Tm`'s conventions, which are
+ * also C's: `tm_year` counts from 1900 and `tm_mon` from 0. Where C has
+ * `tm_gmtoff` and `tm_zone` they follow, with `tm_zone` always null since
+ * the C side cannot borrow `Tm`'s string. `Tm::to_c_tm` makes one.
+ */
+#[cfg(not(target_os = "win32"))]
+pub struct CTm {
+    tm_sec: c_int,
+    tm_min: c_int,
+    tm_hour: c_int,
+    tm_mday: c_int,
+    tm_mon: c_int,
+    tm_year: c_int,
+    tm_wday: c_int,
+    tm_yday: c_int,
+    tm_isdst: c_int,
+    tm_gmtoff: c_long,
+    tm_zone: *c_char,
+}
+
+/// A time laid out like the C `struct tm`, which on Windows has no
+/// `tm_gmtoff` or `tm_zone`. `Tm::to_c_tm` makes one.
+#[cfg(target_os = "win32")]
+pub struct CTm {
+    tm_sec: c_int,
+    tm_min: c_int,
+    tm_hour: c_int,
+    tm_mday: c_int,
+    tm_mon: c_int,
+    tm_year: c_int,
+    tm_wday: c_int,
+    tm_yday: c_int,
+    tm_isdst: c_int,
+}
+
+/**
+ * A `Tm` whose zone name is borrowed rather than owned, so making,
+ * copying and formatting one need not allocate. `at_utc_ref` returns one
+ * for a UTC time directly, and `Tm::as_ref` gives a view of any `Tm`.
+ */
+#[deriving(Clone, Eq)]
+pub struct TmRef<'self> {
+    tm_sec: i32,
+    tm_min: i32,
+    tm_hour: i32,
+    tm_mday: i32,
+    tm_mon: i32,
+    tm_year: i32,
+    tm_wday: i32,
+    tm_yday: i32,
+    tm_isdst: i32,
+    tm_gmtoff: i32,
+    tm_zone: &'self str,
+    tm_nsec: i32,
+}
+
+impl<'self> TmRef<'self> {
+    /// Returns an owned copy of the time, allocating its zone name.
+    pub fn to_tm(&self) -> Tm {
+        Tm {
+            tm_sec: self.tm_sec,
+            tm_min: self.tm_min,
+            tm_hour: self.tm_hour,
+            tm_mday: self.tm_mday,
+            tm_mon: self.tm_mon,
+            tm_year: self.tm_year,
+            tm_wday: self.tm_wday,
+            tm_yday: self.tm_yday,
+            tm_isdst: self.tm_isdst,
+            tm_gmtoff: self.tm_gmtoff,
+            tm_zone: self.tm_zone.to_owned(),
+            tm_nsec: self.tm_nsec,
+        }
+    }
+
+    /// Formats the time according to the format string, as `strftime`.
+    pub fn strftime(&self, format: &str) -> ~str {
+        do_strftime(format, self)
+    }
+}
+
 pub fn empty_tm() -> Tm {
     // 64 is the max size of the timezone buffer allocated on windows
     // in rust_localtime. In glibc the max timezone size is supposedly 3.
@@ -152,6 +499,212 @@ pub fn at_utc(clock: Timespec) -> Tm {
     }
 }
 
+/**
+ * Returns the specified time in UTC, like `at_utc`, but computed entirely
+ * in Rust rather than by calling `gmtime`. Pre-epoch timestamps are
+ * supported and the proleptic Gregorian calendar is used throughout.
+ */
+pub fn at_utc_pure(clock: Timespec) -> Tm {
+    at_utc_ref(clock).to_tm()
+}
+
+/**
+ * Returns the specified time in UTC as a `TmRef`, computed as by
+ * `at_utc_pure`. The zone name is a static string, so unlike `at_utc_pure`
+ * this does not allocate.
+ */
+pub fn at_utc_ref(clock: Timespec) -> TmRef<'static> {
+    static SECS_PER_DAY: i64 = 86400;
+    // Days before the first of each month in a non-leap year.
+    static CUMULATIVE_DAYS: [i64, ..12] =
+        [0, 31, 59, 90, 120, 151, 181, 212, 243, 273, 304, 334];
+
+    let Timespec { sec, nsec } = clock;
+
+    // Floor division, so that pre-epoch times land on the previous day.
+    let mut days = sec / SECS_PER_DAY;
+    let mut secs_of_day = sec % SECS_PER_DAY;
+    if secs_of_day < 0 {
+        days -= 1;
+        secs_of_day += SECS_PER_DAY;
+    }
+
+    // Convert a day count into a civil date by counting 400-year eras
+    // starting on March 1st, so that leap days fall at the end of a year.
+    let z = days + 719468;
+    let era = (if z >= 0 { z } else { z - 146096 }) / 146097;
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let mday = doy - (153 * mp + 2) / 5 + 1;
+    let mon = if mp < 10 { mp + 2 } else { mp - 10 };
+    let year = yoe + era * 400 + if mon < 2 { 1 } else { 0 };
+
+    let leap = is_leap_year(year);
+    let yday = CUMULATIVE_DAYS[mon as uint] + mday - 1 + if leap && mon > 1 { 1 } else { 0 };
+
+    let wday = weekday_from_days(days);
+
+    TmRef {
+        tm_sec: (secs_of_day % 60) as i32,
+        tm_min: (secs_of_day / 60 % 60) as i32,
+        tm_hour: (secs_of_day / 3600) as i32,
+        tm_mday: mday as i32,
+        tm_mon: mon as i32,
+        tm_year: (year - 1900) as i32,
+        tm_wday: wday as i32,
+        tm_yday: yday as i32,
+        tm_isdst: 0_i32,
+        tm_gmtoff: 0_i32,
+        tm_zone: "UTC",
+        tm_nsec: nsec,
+    }
+}
+
+/// Returns true if `year` is a leap year in the Gregorian calendar.
+fn is_leap_year(year: i64) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+/// Returns the number of days in the given month, counting from 0 like
+/// `tm_mon`.
+fn days_in_month(year: i64, mon: i64) -> i64 {
+    static DAYS: [i64, ..12] = [31, 28, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31];
+    if mon == 1 && is_leap_year(year) { 29 } else { DAYS[mon as uint] }
+}
+
+/// Returns the number of days from 1970-01-01 to the given date in the
+/// proleptic Gregorian calendar. `mon` counts from 0, like `tm_mon`.
+fn days_from_civil(year: i64, mon: i64, mday: i64) -> i64 {
+    // Count years from March 1st, as `at_utc_pure` does.
+    let y = if mon < 2 { year - 1 } else { year };
+    let era = (if y >= 0 { y } else { y - 399 }) / 400;
+    let yoe = y - era * 400;
+    let mp = if mon < 2 { mon + 10 } else { mon - 2 };
+    let doy = (153 * mp + 2) / 5 + mday - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+/// Returns the day of the week, counting from 0 for Sunday like `tm_wday`,
+/// of the date `days` days after 1970-01-01.
+fn weekday_from_days(days: i64) -> i64 {
+    // 1970-01-01 was a Thursday.
+    ((days + 4) % 7 + 7) % 7
+}
+
+/**
+ * Returns midnight UTC on the `n`th `weekday` of the given month, or `None`
+ * if the month has no such day (such as a fifth Friday in most months).
+ * Negative `n` counts back from the end of the month, so `-1` is the last
+ * one, and `n == 0` is always `None`.
+ *
+ * `year` is the calendar year, such as 2024, while `month` and `weekday`
+ * count from 0 like `tm_mon` and `tm_wday`, so Thanksgiving is
+ * `nth_weekday_of_month(year, 10, 4, 4)`. Fails if `month` or `weekday` is
+ * out of range.
+ */
+pub fn nth_weekday_of_month(year: i32, month: i32, weekday: i32, n: i32) -> Option<Tm> {
+    assert!(month >= 0 && month < 12,
+            "nth_weekday_of_month called with a month outside [0, 12)");
+    assert!(weekday >= 0 && weekday < 7,
+            "nth_weekday_of_month called with a weekday outside [0, 7)");
+
+    let year = year as i64;
+    let mon = month as i64;
+    let len = days_in_month(year, mon);
+    // Days from `a` forward to the next `b`, for weekdays in [0, 7).
+    let days_until = |a: i64, b: i64| (b - a + 7) % 7;
+    let mday = if n > 0 {
+        let first_wday = weekday_from_days(days_from_civil(year, mon, 1));
+        1 + days_until(first_wday, weekday as i64) + 7 * (n as i64 - 1)
+    } else if n < 0 {
+        let last_wday = weekday_from_days(days_from_civil(year, mon, len));
+        len - days_until(weekday as i64, last_wday) + 7 * (n as i64 + 1)
+    } else {
+        return None;
+    };
+
+    if mday < 1 || mday > len {
+        return None;
+    }
+    Some(at_utc_pure(Timespec::new(days_from_civil(year, mon, mday) * 86400, 0)))
+}
+
+/**
+ * A timezone that `at_zone` can convert times into. Unlike `at`, which reads
+ * the process-wide `TZ` setting, conversions through a `TimeZone` do not
+ * depend on global state, so they cannot race with `tzset` calls made by
+ * other tasks.
+ */
+pub trait TimeZone {
+    /// Returns the offset from UTC in seconds, and the zone's abbreviation,
+    /// in effect at the instant `clock`.
+    fn offset_at(&self, clock: &Timespec) -> (i32, ~str);
+}
+
+/// A timezone with a fixed offset from UTC and no daylight saving time.
+#[deriving(Clone, Eq)]
+pub struct FixedOffset {
+    priv offset: i32,
+    priv name: ~str,
+}
+
+impl FixedOffset {
+    /// Creates a zone `offset` seconds east of UTC, abbreviated as `name`.
+    /// Fails unless the offset is less than a day in either direction.
+    pub fn new(offset: i32, name: &str) -> FixedOffset {
+        assert!(offset > -86400 && offset < 86400,
+                "FixedOffset::new called with an offset of a day or more");
+        FixedOffset { offset: offset, name: name.to_owned() }
+    }
+
+    /// Returns the UTC zone.
+    pub fn utc() -> FixedOffset {
+        FixedOffset::new(0, "UTC")
+    }
+}
+
+impl TimeZone for FixedOffset {
+    fn offset_at(&self, _clock: &Timespec) -> (i32, ~str) {
+        (self.offset, self.name.clone())
+    }
+}
+
+/// Returns the specified time in the given timezone, without consulting the
+/// process-wide `TZ` setting.
+pub fn at_zone<Z: TimeZone>(clock: Timespec, zone: &Z) -> Tm {
+    let (offset, name) = zone.offset_at(&clock);
+    let mut tm = at_utc_pure(Timespec::new(clock.sec + offset as i64, clock.nsec));
+    tm.tm_gmtoff = offset;
+    tm.tm_zone = name;
+    tm
+}
+
+/// Returns the offset from UTC, in seconds, of mean solar time at the given
+/// longitude (degrees east, negative for west), rounded to the nearest
+/// second. Each 15 degrees is one hour. Fails if the longitude is outside
+/// `[-180, 180]`.
+///
+/// Combine it with `FixedOffset` and `at_zone` to get local mean solar time:
+///
+/// ```rust
+/// extern mod extra;
+/// use extra::time::{FixedOffset, at_zone, get_time, solar_offset_seconds};
+///
+/// fn main() {
+///     let offset = solar_offset_seconds(-75.0);
+///     let tm = at_zone(get_time(), &FixedOffset::new(offset, "LMT"));
+///     println(tm.strftime("%T"));
+/// }
+/// ```
+pub fn solar_offset_seconds(longitude_deg: f64) -> i32 {
+    assert!(longitude_deg >= -180.0 && longitude_deg <= 180.0,
+            "solar_offset_seconds called with a longitude outside [-180, 180]");
+    (longitude_deg / 15.0 * 3600.0).round() as i32
+}
+
 /// Returns the current time in UTC
 pub fn now_utc() -> Tm {
     at_utc(get_time())
@@ -176,16 +729,219 @@ pub fn now() -> Tm {
 
 /// Parses the time from the string according to the format string.
 pub fn strptime(s: &str, format: &str) -> Result<Tm, ~str> {
-    do_strptime(s, format)
+    do_strptime(s, format, false)
+}
+
+/**
+ * Parses the time like `strptime`, but lets numeric fields other than `%Y`
+ * and `%z` omit their zero padding, so "2009-2-3" parses with "%Y-%m-%d".
+ *
+ * Each field reads greedily up to its usual width, so adjacent unpadded
+ * fields without a separator between them, as in "%m%d", are ambiguous and
+ * may fail to parse.
+ */
+pub fn strptime_lenient(s: &str, format: &str) -> Result<Tm, ~str> {
+    do_strptime(s, format, true)
 }
 
-/// Formats the time according to the format string.
+/**
+ * Formats the time according to the format string.
+ *
+ * Fields are printed as stored, without validation. In particular a `Tm`
+ * whose `tm_mday` was never set, such as `empty_tm()`, has day 0 and prints
+ * it as "00" with `%d` and " 0" with `%e`.
+ */
 pub fn strftime(format: &str, tm: &Tm) -> ~str {
-    do_strftime(format, tm)
+    do_strftime(format, &tm.as_ref())
+}
+
+/**
+ * Parses an `@`-prefixed count of seconds since the epoch, as accepted by
+ * `date -d @1234567890`, into a `Timespec`. The count may be negative and
+ * may have a fractional part, e.g. "@1234567890.5"; digits beyond
+ * nanoseconds are ignored.
+ */
+pub fn parse_at_timestamp(s: &str) -> Result<Timespec, ~str> {
+    let s = s.trim();
+    let invalid = || format!("Invalid @timestamp: '{}'", s);
+    if !s.starts_with("@") {
+        return Err(invalid());
+    }
+    let s_num = s.slice_from(1);
+    let (negative, digits) = if s_num.starts_with("-") {
+        (true, s_num.slice_from(1))
+    } else {
+        (false, s_num)
+    };
+    let (whole, frac) = match digits.find('.') {
+        Some(i) => (digits.slice_to(i), digits.slice_from(i + 1)),
+        None => (digits, "")
+    };
+    if whole.is_empty() || !whole.iter().all(char::is_digit) ||
+       !frac.iter().all(char::is_digit) || (digits.contains_char('.') && frac.is_empty()) {
+        return Err(invalid());
+    }
+
+    let sec: i64 = match from_str(whole) {
+        Some(sec) => sec,
+        None => return Err(invalid())
+    };
+    let mut nsec = 0_i32;
+    let mut multiplier = NSEC_PER_SEC / 10;
+    for c in frac.iter().take(9) {
+        nsec += (c as i32 - '0' as i32) * multiplier;
+        multiplier /= 10;
+    }
+
+    if !negative {
+        Ok(Timespec::new(sec, nsec))
+    } else if nsec == 0 {
+        Ok(Timespec::new(-sec, 0))
+    } else {
+        // Keep nsec positive, as Timespec requires.
+        Ok(Timespec::new(-sec - 1, NSEC_PER_SEC - nsec))
+    }
+}
+
+/**
+ * Parses the time like `strptime`, then checks that the day of the week
+ * agrees with the date. If `format` contains a weekday (`%a`, `%A`, `%c`,
+ * `%u` or `%w`) and a year (`%Y`, `%y`, `%C`, `%c`, `%D`, `%F`, `%x` or
+ * `%v`), and the input supplied a day of the month, a weekday that
+ * contradicts the date, as in "Mon, 13 Feb 2009" (a Friday), is an error
+ * rather than being silently kept. Without a year there is no date to
+ * check against.
+ */
+pub fn strptime_strict(s: &str, format: &str) -> Result<Tm, ~str> {
+    let tm = match strptime(s, format) {
+        Ok(tm) => tm,
+        Err(e) => return Err(e)
+    };
+
+    let mut has_weekday = false;
+    let mut has_year = false;
+    let mut chars = format.iter();
+    loop {
+        match chars.next() {
+            Some('%') => match chars.next() {
+                Some(c) => {
+                    match c {
+                        'a' | 'A' | 'c' | 'u' | 'w' => has_weekday = true,
+                        _ => ()
+                    }
+                    match c {
+                        'Y' | 'y' | 'C' | 'c' | 'D' | 'F' | 'x' | 'v' => has_year = true,
+                        _ => ()
+                    }
+                }
+                None => ()
+            },
+            Some(_) => (),
+            None => break
+        }
+    }
+
+    if has_weekday && has_year && tm.tm_mday != 0_i32 {
+        let days = days_from_civil(tm.tm_year as i64 + 1900, tm.tm_mon as i64,
+                                   tm.tm_mday as i64);
+        if weekday_from_days(days) != tm.tm_wday as i64 {
+            return Err(format!("Weekday does not match the date: {}", s));
+        }
+    }
+    Ok(tm)
+}
+
+/**
+ * Parses a date in the RFC 2822 format used by email headers, such as
+ * "Fri, 13 Feb 2009 23:31:30 +0000".
+ *
+ * The day of the week is optional, but must agree with the date if given;
+ * `tm_wday` is always worked out from the date. The day of the month may be
+ * a single digit. The zone is either a numeric `+HHMM` / `-HHMM` offset or
+ * one of the named zones RFC 2822 allows (UT, GMT, EST, EDT, CST, CDT, MST,
+ * MDT, PST and PDT), and sets `tm_gmtoff` accordingly. This accepts the
+ * output of both `rfc822` and `rfc822z`.
+ */
+pub fn strptime_rfc2822(s: &str) -> Result<Tm, ~str> {
+    static NAMED_ZONES: [(&'static str, i32), ..10] = [
+        ("UT", 0), ("GMT", 0),
+        ("EST", -5), ("EDT", -4),
+        ("CST", -6), ("CDT", -5),
+        ("MST", -7), ("MDT", -6),
+        ("PST", -8), ("PDT", -7),
+    ];
+
+    let s = s.trim();
+    let (weekday, rest) = match s.find(',') {
+        Some(i) => (Some(s.slice_to(i)), s.slice_from(i + 1).trim_left()),
+        None => (None, s)
+    };
+    let (date, zone) = match rest.rfind(' ') {
+        Some(i) => (rest.slice_to(i), rest.slice_from(i + 1)),
+        None => return Err(~"Invalid RFC 2822 date: missing zone")
+    };
+
+    // `%d` needs two digits, but RFC 2822 allows "3 Feb 2009".
+    let parsed = if date.len() > 1 && date[1] == ' ' as u8 {
+        strptime(~"0" + date, "%d %b %Y %T")
+    } else {
+        strptime(date, "%d %b %Y %T")
+    };
+    let mut tm = match parsed {
+        Ok(tm) => tm,
+        Err(e) => return Err(e)
+    };
+
+    // The weekday is optional, but must match the date when given.
+    let days = days_from_civil(tm.tm_year as i64 + 1900, tm.tm_mon as i64, tm.tm_mday as i64);
+    tm.tm_wday = weekday_from_days(days) as i32;
+    match weekday {
+        Some(day) => match strptime(day, "%a") {
+            Ok(day_tm) => if day_tm.tm_wday != tm.tm_wday {
+                return Err(format!("Weekday does not match the date: {}", s));
+            },
+            Err(e) => return Err(e)
+        },
+        None => ()
+    }
+
+    let (sign, digits) = match zone.char_at(0) {
+        '+' => (1_i32, zone.slice_from(1)),
+        '-' => (-1_i32, zone.slice_from(1)),
+        _ => (0_i32, zone)
+    };
+    if sign != 0 {
+        if digits.len() != 4 || !digits.iter().all(char::is_digit) {
+            return Err(format!("Invalid zone offset: '{}'", zone));
+        }
+        let hhmm: i32 = from_str(digits).unwrap();
+        if hhmm % 100 > 59 {
+            return Err(format!("Invalid zone offset: '{}'", zone));
+        }
+        tm.tm_gmtoff = sign * (hhmm / 100 * 3600 + hhmm % 100 * 60);
+        tm.tm_zone = if tm.tm_gmtoff == 0 { ~"UTC" } else { ~"" };
+    } else {
+        match NAMED_ZONES.iter().find(|&&(name, _)| name == zone) {
+            Some(&(_, hours)) => {
+                tm.tm_gmtoff = hours * 3600;
+                tm.tm_zone = if hours == 0 { ~"UTC" } else { zone.to_owned() };
+            }
+            None => return Err(format!("Invalid zone: '{}'", zone))
+        }
+    }
+    Ok(tm)
 }
 
 impl Tm {
-    /// Convert time to the seconds from January 1, 1970
+    /**
+     * Convert time to the seconds from January 1, 1970
+     *
+     * A time with a non-zero `tm_gmtoff` is interpreted in the local
+     * timezone, and `tm_isdst` then decides which offset applies to
+     * wall-clock times near a DST transition: 1 means daylight saving time
+     * is in effect, 0 means it is not, and -1 leaves it to the C runtime to
+     * work out from the timezone rules. Times from `strptime` carry -1.
+     */
     pub fn to_timespec(&self) -> Timespec {
         #[fixed_stack_segment]; #[inline(never)];
 
@@ -199,6 +955,102 @@ impl Tm {
         }
     }
 
+    /// Returns a copy of this time with `tm_isdst` set to `isdst`
+    /// (1 = on, 0 = off, -1 = unknown).
+    pub fn with_dst(&self, isdst: i32) -> Tm {
+        let mut tm = self.clone();
+        tm.tm_isdst = isdst;
+        tm
+    }
+
+    /**
+     * Compares two times by what their clocks show: year, month, day of the
+     * month, hour, minute, second and finally nanosecond, ignoring the
+     * timezone. This groups events by local calendar date, and differs from
+     * comparing the instants given by `to_timespec`: 10:00 in Los Angeles
+     * sorts before 11:00 in New York even though it happens two hours later.
+     */
+    pub fn field_cmp(&self, other: &Tm) -> Ordering {
+        let fields = [self.tm_year, self.tm_mon, self.tm_mday, self.tm_hour,
+                      self.tm_min, self.tm_sec, self.tm_nsec];
+        let other_fields = [other.tm_year, other.tm_mon, other.tm_mday,
+                            other.tm_hour, other.tm_min, other.tm_sec,
+                            other.tm_nsec];
+        for (a, b) in fields.iter().zip(other_fields.iter()) {
+            match a.cmp(b) {
+                Equal => (),
+                ord => return ord
+            }
+        }
+        Equal
+    }
+
+    /**
+     * Rounds the instant this time represents to the nearest multiple of
+     * `step_ns` nanoseconds since the epoch, as `Timespec::round_to` does,
+     * e.g. to align timestamps to 15 minute buckets.
+     *
+     * The instant is worked out from the fields and `tm_gmtoff`, not the
+     * process-wide `TZ` setting, and the result keeps this time's
+     * `tm_gmtoff` and `tm_zone`, even if a daylight saving transition
+     * happens in between.
+     */
+    pub fn round_to(&self, step_ns: u64) -> Tm {
+        let days = days_from_civil(self.tm_year as i64 + 1900, self.tm_mon as i64,
+                                   self.tm_mday as i64);
+        let secs = days * 86400 + self.tm_hour as i64 * 3600 + self.tm_min as i64 * 60 +
+            self.tm_sec as i64 - self.tm_gmtoff as i64;
+        let rounded = Timespec::new(secs, self.tm_nsec).round_to(step_ns);
+        at_zone(rounded, &FixedOffset::new(self.tm_gmtoff, self.tm_zone.as_slice()))
+    }
+
+    /**
+     * Returns the number of complete years from this date to `later`, as
+     * when computing someone's age from their birthday: the year difference,
+     * less one if the anniversary of this date has not yet been reached in
+     * `later`'s year. Only the calendar dates are compared, not the times of
+     * day or timezones. The result is negative if `later` is earlier.
+     *
+     * A February 29th date has its anniversary on February 28th in years
+     * that are not leap years.
+     */
+    pub fn whole_years_until(&self, later: &Tm) -> i32 {
+        let later_year = later.tm_year as i64 + 1900;
+        let anniversary_mday = if self.tm_mon == 1 && self.tm_mday == 29 &&
+                                  !is_leap_year(later_year) {
+            28
+        } else {
+            self.tm_mday
+        };
+        let years = later.tm_year - self.tm_year;
+        if (later.tm_mon, later.tm_mday) < (self.tm_mon, anniversary_mday) {
+            years - 1
+        } else {
+            years
+        }
+    }
+
+    /**
+     * Returns an iterator over the dates falling on `weekday` (0 for Sunday
+     * to 6 for Saturday), one week apart, starting with the first such date
+     * on or after this one. Fails if `weekday` is out of range.
+     *
+     * Each date keeps this time's time of day, offset and zone fields
+     * unchanged, as a recurring calendar entry would; no adjustment is made
+     * if a daylight saving transition happens in between. The weekday is
+     * worked out from the date, so `tm_wday` need not be set.
+     */
+    pub fn weekly_iter(&self, weekday: i32) -> WeekdayIter {
+        assert!(weekday >= 0 && weekday < 7, "Tm::weekly_iter called with an invalid weekday");
+        let days = days_from_civil(self.tm_year as i64 + 1900, self.tm_mon as i64,
+                                   self.tm_mday as i64);
+        let wday = weekday_from_days(days);
+        WeekdayIter {
+            template: self.clone(),
+            next_day: days + ((weekday as i64 - wday) % 7 + 7) % 7
+        }
+    }
+
     /// Convert time to the local timezone
     pub fn to_local(&self) -> Tm {
         at(self.to_timespec())
@@ -220,6 +1072,60 @@ impl Tm {
         strftime(format, self)
     }
 
+    /// Returns this time laid out as a C `struct tm`, dropping the
+    /// nanoseconds and the zone name.
+    #[cfg(not(target_os = "win32"))]
+    pub fn to_c_tm(&self) -> CTm {
+        CTm {
+            tm_sec: self.tm_sec as c_int,
+            tm_min: self.tm_min as c_int,
+            tm_hour: self.tm_hour as c_int,
+            tm_mday: self.tm_mday as c_int,
+            tm_mon: self.tm_mon as c_int,
+            tm_year: self.tm_year as c_int,
+            tm_wday: self.tm_wday as c_int,
+            tm_yday: self.tm_yday as c_int,
+            tm_isdst: self.tm_isdst as c_int,
+            tm_gmtoff: self.tm_gmtoff as c_long,
+            tm_zone: ptr::null(),
+        }
+    }
+
+    /// Returns this time laid out as a C `struct tm`, dropping the
+    /// nanoseconds, the UTC offset and the zone name.
+    #[cfg(target_os = "win32")]
+    pub fn to_c_tm(&self) -> CTm {
+        CTm {
+            tm_sec: self.tm_sec as c_int,
+            tm_min: self.tm_min as c_int,
+            tm_hour: self.tm_hour as c_int,
+            tm_mday: self.tm_mday as c_int,
+            tm_mon: self.tm_mon as c_int,
+            tm_year: self.tm_year as c_int,
+            tm_wday: self.tm_wday as c_int,
+            tm_yday: self.tm_yday as c_int,
+            tm_isdst: self.tm_isdst as c_int,
+        }
+    }
+
+    /// Returns a view of this time that borrows its zone name.
+    pub fn as_ref<'a>(&'a self) -> TmRef<'a> {
+        TmRef {
+            tm_sec: self.tm_sec,
+            tm_min: self.tm_min,
+            tm_hour: self.tm_hour,
+            tm_mday: self.tm_mday,
+            tm_mon: self.tm_mon,
+            tm_year: self.tm_year,
+            tm_wday: self.tm_wday,
+            tm_yday: self.tm_yday,
+            tm_isdst: self.tm_isdst,
+            tm_gmtoff: self.tm_gmtoff,
+            tm_zone: self.tm_zone.as_slice(),
+            tm_nsec: self.tm_nsec,
+        }
+    }
+
     /**
      * Returns a time string formatted according to RFC 822.
      *
@@ -264,7 +1170,134 @@ impl Tm {
     }
 }
 
-fn do_strptime(s: &str, format: &str) -> Result<Tm, ~str> {
+/// An iterator over dates one week apart, returned by `Tm::weekly_iter`.
+pub struct WeekdayIter {
+    priv template: Tm,
+    // Days since 1970-01-01 of the next date to yield.
+    priv next_day: i64
+}
+
+impl Iterator<Tm> for WeekdayIter {
+    fn next(&mut self) -> Option<Tm> {
+        let t = &self.template;
+        let secs = self.next_day * 86400 +
+            (t.tm_hour * 3600 + t.tm_min * 60 + t.tm_sec) as i64;
+        let mut tm = at_utc_pure(Timespec::new(secs, t.tm_nsec));
+        tm.tm_isdst = t.tm_isdst;
+        tm.tm_gmtoff = t.tm_gmtoff;
+        tm.tm_zone = t.tm_zone.clone();
+        self.next_day += 7;
+        Some(tm)
+    }
+}
+
+/**
+ * A `Tm` that compares by the instant it denotes, as given by
+ * `to_timespec`, rather than field by field. The same moment shown in two
+ * timezones is equal, and times sort chronologically whatever their zones,
+ * so wrapped times can be sorted or used as `TreeMap` keys. `Tm` itself
+ * keeps its field-wise `Eq`.
+ */
+#[deriving(Clone, DeepClone)]
+pub struct Instant(Tm);
+
+impl Instant {
+    /// Returns the instant as a `Timespec`.
+    pub fn to_timespec(&self) -> Timespec {
+        let Instant(ref tm) = *self;
+        tm.to_timespec()
+    }
+}
+
+impl Eq for Instant {
+    fn eq(&self, other: &Instant) -> bool {
+        self.to_timespec() == other.to_timespec()
+    }
+}
+
+impl Ord for Instant {
+    fn lt(&self, other: &Instant) -> bool {
+        self.to_timespec() < other.to_timespec()
+    }
+}
+
+impl TotalEq for Instant {
+    fn equals(&self, other: &Instant) -> bool {
+        *self == *other
+    }
+}
+
+impl TotalOrd for Instant {
+    fn cmp(&self, other: &Instant) -> Ordering {
+        let (a, b) = (self.to_timespec(), other.to_timespec());
+        if a < b { Less } else if a > b { Greater } else { Equal }
+    }
+}
+
+/// Extension methods for generating random times, for fake records and
+/// fixtures.
+pub trait TimeRng {
+    /**
+     * Returns a random valid date at midnight UTC, in a year between
+     * `min_year` and `max_year` inclusive. The year and month are uniform
+     * and the day is uniform within the month, so February 29th only occurs
+     * in leap years. Fails if `min_year > max_year`.
+     */
+    fn gen_date(&mut self, min_year: i32, max_year: i32) -> Tm;
+
+    /**
+     * Returns `count` random times drawn independently and uniformly from
+     * `[start, end)`, sorted in ascending order, like the arrival times of
+     * a Poisson process conditioned on `count` arrivals. The sorted order
+     * comes from normalized sums of exponential gaps, so no sort is needed.
+     * Fails if `count > 0` and the window is empty, or if it spans more
+     * than about 292 years.
+     */
+    fn gen_event_times(&mut self, start: Timespec, end: Timespec, count: uint) -> ~[Timespec];
+}
+
+impl<R: Rng> TimeRng for R {
+    fn gen_date(&mut self, min_year: i32, max_year: i32) -> Tm {
+        assert!(min_year <= max_year, "gen_date called with `min_year` > `max_year`");
+        let year = self.gen_integer_range(min_year as i64, max_year as i64 + 1);
+        let mon = self.gen_integer_range(0i64, 12);
+        let mday = self.gen_integer_range(1i64, days_in_month(year, mon) + 1);
+        at_utc_pure(Timespec::new(days_from_civil(year, mon, mday) * 86400, 0))
+    }
+
+    fn gen_event_times(&mut self, start: Timespec, end: Timespec, count: uint) -> ~[Timespec] {
+        if count == 0 {
+            return ~[];
+        }
+        assert!(start < end, "gen_event_times called with an empty window");
+        let span = end.sec.checked_sub(&start.sec)
+            .and_then(|sec| sec.checked_mul(&(NSEC_PER_SEC as i64)))
+            .and_then(|ns| ns.checked_add(&((end.nsec - start.nsec) as i64)));
+        let span = match span {
+            Some(span) => span as u64,
+            None => fail2!("gen_event_times called with too wide a window")
+        };
+
+        // The partial sums of `count + 1` exponential gaps, divided by the
+        // total, are distributed as `count` sorted uniforms.
+        let mut sums = vec::with_capacity(count);
+        let mut total = 0.0;
+        for _ in range(0, count) {
+            total += *self.gen::<Exp1>();
+            sums.push(total);
+        }
+        total += *self.gen::<Exp1>();
+
+        let nsec_per_sec = NSEC_PER_SEC as u64;
+        sums.move_iter().map(|sum| {
+            let offset = num::min((sum / total * span as f64) as u64, span - 1);
+            Timespec::new_normalized(start.sec + (offset / nsec_per_sec) as i64,
+                                     start.nsec as i64 + (offset % nsec_per_sec) as i64)
+        }).collect()
+    }
+}
+
+fn do_strptime(s: &str, format: &str, lenient: bool) -> Result<Tm, ~str> {
     fn match_str(s: &str, pos: uint, needle: &str) -> bool {
         let mut i = pos;
         for ch in needle.byte_iter() {
@@ -294,27 +1327,56 @@ fn do_strptime(s: &str, format: &str) -> Result<Tm, ~str> {
         None
     }
 
-    fn match_digits(ss: &str, pos: uint, digits: uint, ws: bool)
-      -> Option<(i32, uint)> {
+    // Matches "am" or "pm" in any case, optionally dotted as in "a.m.",
+    // returning the hour offset and the position after the match.
+    fn match_meridiem(ss: &str, pos: uint) -> Option<(i32, uint)> {
+        let at = |i: uint| if i < ss.len() { ss[i] as char } else { '\0' };
+        let offset = match at(pos) {
+            'a' | 'A' => 0_i32,
+            'p' | 'P' => 12_i32,
+            _ => return None
+        };
+        let dotted = at(pos + 1u) == '.';
+        let mut pos = if dotted { pos + 2u } else { pos + 1u };
+        match at(pos) {
+            'm' | 'M' => pos += 1u,
+            _ => return None
+        }
+        if dotted && at(pos) == '.' {
+            pos += 1u;
+        }
+        Some((offset, pos))
+    }
+
+    // Matches exactly `digits` digits, or when `lenient` is set, from one
+    // up to `digits` digits, stopping early at the first non-digit. With
+    // `ws`, spaces may pad the number; in lenient mode only before it, so
+    // that a space after a single digit is left for the format.
+    fn match_digits(ss: &str, pos: uint, digits: uint, ws: bool,
+                    lenient: bool) -> Option<(i32, uint)> {
         let mut pos = pos;
         let len = ss.len();
         let mut value = 0_i32;
+        let mut seen_digit = false;
 
         let mut i = 0u;
         while i < digits {
             if pos >= len {
+                if lenient && seen_digit { break; }
                 return None;
             }
             let range = ss.char_range_at(pos);
-            pos = range.next;
 
             match range.ch {
               '0' .. '9' => {
                 value = value * 10_i32 + (range.ch as i32 - '0' as i32);
+                seen_digit = true;
               }
-              ' ' if ws => (),
+              ' ' if ws && !(lenient && seen_digit) => (),
+              _ if lenient && seen_digit => break,
               _ => return None
             }
+            pos = range.next;
             i += 1u;
         }
 
@@ -349,8 +1411,9 @@ fn do_strptime(s: &str, format: &str) -> Result<Tm, ~str> {
     }
 
     fn match_digits_in_range(ss: &str, pos: uint, digits: uint, ws: bool,
-                             min: i32, max: i32) -> Option<(i32, uint)> {
-        match match_digits(ss, pos, digits, ws) {
+                             lenient: bool, min: i32, max: i32)
+      -> Option<(i32, uint)> {
+        match match_digits(ss, pos, digits, ws, lenient) {
           Some((val, pos)) if val >= min && val <= max => {
             Some((val, pos))
           }
@@ -358,19 +1421,39 @@ fn do_strptime(s: &str, format: &str) -> Result<Tm, ~str> {
         }
     }
 
+    // Describes a parse failure at byte offset `pos` of the input
+    fn error_at(s: &str, pos: uint, msg: &str) -> ~str {
+        if pos < s.len() {
+            format!("{} at position {}: '{}'", msg, pos, s.char_at(pos))
+        } else {
+            format!("{} at position {}: end of input", msg, pos)
+        }
+    }
+
     fn parse_char(s: &str, pos: uint, c: char) -> Result<uint, ~str> {
-        let range = s.char_range_at(pos);
+        if pos < s.len() {
+            let range = s.char_range_at(pos);
+            if c == range.ch {
+                return Ok(range.next);
+            }
+        }
+        Err(error_at(s, pos, format!("Expected '{}'", c)))
+    }
 
-        if c == range.ch {
-            Ok(range.next)
-        } else {
-            Err(format!("Expected {}, found {}",
-                str::from_char(c),
-                str::from_char(range.ch)))
+    // Skips any amount of whitespace, including none, as `%n` and `%t` do
+    fn skip_whitespace(s: &str, pos: uint) -> uint {
+        let mut pos = pos;
+        while pos < s.len() {
+            let range = s.char_range_at(pos);
+            if !range.ch.is_whitespace() {
+                break;
+            }
+            pos = range.next;
         }
+        pos
     }
 
-    fn parse_type(s: &str, pos: uint, ch: char, tm: &mut Tm)
+    fn parse_type(s: &str, pos: uint, ch: char, tm: &mut Tm, lenient: bool)
       -> Result<uint, ~str> {
         match ch {
           'A' => match match_strs(s, pos, [
@@ -383,7 +1466,7 @@ fn do_strptime(s: &str, format: &str) -> Result<Tm, ~str> {
               (~"Saturday", 6_i32)
           ]) {
             Some(item) => { let (v, pos) = item; tm.tm_wday = v; Ok(pos) }
-            None => Err(~"Invalid day")
+            None => Err(error_at(s, pos, "Invalid day"))
           },
           'a' => match match_strs(s, pos, [
               (~"Sun", 0_i32),
@@ -395,7 +1478,7 @@ fn do_strptime(s: &str, format: &str) -> Result<Tm, ~str> {
               (~"Sat", 6_i32)
           ]) {
             Some(item) => { let (v, pos) = item; tm.tm_wday = v; Ok(pos) }
-            None => Err(~"Invalid day")
+            None => Err(error_at(s, pos, "Invalid day"))
           },
           'B' => match match_strs(s, pos, [
               (~"January", 0_i32),
@@ -412,7 +1495,7 @@ fn do_strptime(s: &str, format: &str) -> Result<Tm, ~str> {
               (~"December", 11_i32)
           ]) {
             Some(item) => { let (v, pos) = item; tm.tm_mon = v; Ok(pos) }
-            None => Err(~"Invalid month")
+            None => Err(error_at(s, pos, "Invalid month"))
           },
           'b' | 'h' => match match_strs(s, pos, [
               (~"Jan", 0_i32),
@@ -429,44 +1512,44 @@ fn do_strptime(s: &str, format: &str) -> Result<Tm, ~str> {
               (~"Dec", 11_i32)
           ]) {
             Some(item) => { let (v, pos) = item; tm.tm_mon = v; Ok(pos) }
-            None => Err(~"Invalid month")
+            None => Err(error_at(s, pos, "Invalid month"))
           },
-          'C' => match match_digits_in_range(s, pos, 2u, false, 0_i32,
+          'C' => match match_digits_in_range(s, pos, 2u, false, lenient, 0_i32,
                                              99_i32) {
             Some(item) => {
                 let (v, pos) = item;
                   tm.tm_year += (v * 100_i32) - 1900_i32;
                   Ok(pos)
               }
-            None => Err(~"Invalid year")
+            None => Err(error_at(s, pos, "Invalid year"))
           },
           'c' => {
-            parse_type(s, pos, 'a', &mut *tm)
+            parse_type(s, pos, 'a', &mut *tm, lenient)
                 .and_then(|pos| parse_char(s, pos, ' '))
-                .and_then(|pos| parse_type(s, pos, 'b', &mut *tm))
+                .and_then(|pos| parse_type(s, pos, 'b', &mut *tm, lenient))
                 .and_then(|pos| parse_char(s, pos, ' '))
-                .and_then(|pos| parse_type(s, pos, 'e', &mut *tm))
+                .and_then(|pos| parse_type(s, pos, 'e', &mut *tm, lenient))
                 .and_then(|pos| parse_char(s, pos, ' '))
-                .and_then(|pos| parse_type(s, pos, 'T', &mut *tm))
+                .and_then(|pos| parse_type(s, pos, 'T', &mut *tm, lenient))
                 .and_then(|pos| parse_char(s, pos, ' '))
-                .and_then(|pos| parse_type(s, pos, 'Y', &mut *tm))
+                .and_then(|pos| parse_type(s, pos, 'Y', &mut *tm, lenient))
           }
           'D' | 'x' => {
-            parse_type(s, pos, 'm', &mut *tm)
+            parse_type(s, pos, 'm', &mut *tm, lenient)
                 .and_then(|pos| parse_char(s, pos, '/'))
-                .and_then(|pos| parse_type(s, pos, 'd', &mut *tm))
+                .and_then(|pos| parse_type(s, pos, 'd', &mut *tm, lenient))
                 .and_then(|pos| parse_char(s, pos, '/'))
-                .and_then(|pos| parse_type(s, pos, 'y', &mut *tm))
+                .and_then(|pos| parse_type(s, pos, 'y', &mut *tm, lenient))
           }
-          'd' => match match_digits_in_range(s, pos, 2u, false, 1_i32,
+          'd' => match match_digits_in_range(s, pos, 2u, false, lenient, 1_i32,
                                              31_i32) {
             Some(item) => { let (v, pos) = item; tm.tm_mday = v; Ok(pos) }
-            None => Err(~"Invalid day of the month")
+            None => Err(error_at(s, pos, "Invalid day of the month"))
           },
-          'e' => match match_digits_in_range(s, pos, 2u, true, 1_i32,
+          'e' => match match_digits_in_range(s, pos, 2u, true, lenient, 1_i32,
                                              31_i32) {
             Some(item) => { let (v, pos) = item; tm.tm_mday = v; Ok(pos) }
-            None => Err(~"Invalid day of the month")
+            None => Err(error_at(s, pos, "Invalid day of the month"))
           },
           'f' => {
             let (val, pos) = match_fractional_seconds(s, pos);
@@ -474,160 +1557,153 @@ fn do_strptime(s: &str, format: &str) -> Result<Tm, ~str> {
             Ok(pos)
           }
           'F' => {
-            parse_type(s, pos, 'Y', &mut *tm)
+            parse_type(s, pos, 'Y', &mut *tm, lenient)
                 .and_then(|pos| parse_char(s, pos, '-'))
-                .and_then(|pos| parse_type(s, pos, 'm', &mut *tm))
+                .and_then(|pos| parse_type(s, pos, 'm', &mut *tm, lenient))
                 .and_then(|pos| parse_char(s, pos, '-'))
-                .and_then(|pos| parse_type(s, pos, 'd', &mut *tm))
+                .and_then(|pos| parse_type(s, pos, 'd', &mut *tm, lenient))
           }
           'H' => {
-            match match_digits_in_range(s, pos, 2u, false, 0_i32, 23_i32) {
+            match match_digits_in_range(s, pos, 2u, false, lenient, 0_i32, 23_i32) {
               Some(item) => { let (v, pos) = item; tm.tm_hour = v; Ok(pos) }
-              None => Err(~"Invalid hour")
+              None => Err(error_at(s, pos, "Invalid hour"))
             }
           }
           'I' => {
-            match match_digits_in_range(s, pos, 2u, false, 1_i32, 12_i32) {
+            match match_digits_in_range(s, pos, 2u, false, lenient, 1_i32, 12_i32) {
               Some(item) => {
                   let (v, pos) = item;
                   tm.tm_hour = if v == 12_i32 { 0_i32 } else { v };
                   Ok(pos)
               }
-              None => Err(~"Invalid hour")
+              None => Err(error_at(s, pos, "Invalid hour"))
             }
           }
           'j' => {
-            match match_digits_in_range(s, pos, 3u, false, 1_i32, 366_i32) {
+            match match_digits_in_range(s, pos, 3u, false, lenient, 1_i32, 366_i32) {
               Some(item) => {
                 let (v, pos) = item;
                 tm.tm_yday = v - 1_i32;
                 Ok(pos)
               }
-              None => Err(~"Invalid day of year")
+              None => Err(error_at(s, pos, "Invalid day of year"))
             }
           }
           'k' => {
-            match match_digits_in_range(s, pos, 2u, true, 0_i32, 23_i32) {
+            match match_digits_in_range(s, pos, 2u, true, lenient, 0_i32, 23_i32) {
               Some(item) => { let (v, pos) = item; tm.tm_hour = v; Ok(pos) }
-              None => Err(~"Invalid hour")
+              None => Err(error_at(s, pos, "Invalid hour"))
             }
           }
           'l' => {
-            match match_digits_in_range(s, pos, 2u, true, 1_i32, 12_i32) {
+            match match_digits_in_range(s, pos, 2u, true, lenient, 1_i32, 12_i32) {
               Some(item) => {
                   let (v, pos) = item;
                   tm.tm_hour = if v == 12_i32 { 0_i32 } else { v };
                   Ok(pos)
               }
-              None => Err(~"Invalid hour")
+              None => Err(error_at(s, pos, "Invalid hour"))
             }
           }
           'M' => {
-            match match_digits_in_range(s, pos, 2u, false, 0_i32, 59_i32) {
+            match match_digits_in_range(s, pos, 2u, false, lenient, 0_i32, 59_i32) {
               Some(item) => { let (v, pos) = item; tm.tm_min = v; Ok(pos) }
-              None => Err(~"Invalid minute")
+              None => Err(error_at(s, pos, "Invalid minute"))
             }
           }
           'm' => {
-            match match_digits_in_range(s, pos, 2u, false, 1_i32, 12_i32) {
+            match match_digits_in_range(s, pos, 2u, false, lenient, 1_i32, 12_i32) {
               Some(item) => {
                 let (v, pos) = item;
                 tm.tm_mon = v - 1_i32;
                 Ok(pos)
               }
-              None => Err(~"Invalid month")
+              None => Err(error_at(s, pos, "Invalid month"))
             }
           }
-          'n' => parse_char(s, pos, '\n'),
-          'P' => match match_strs(s, pos,
-                                  [(~"am", 0_i32), (~"pm", 12_i32)]) {
-
+          'n' => Ok(skip_whitespace(s, pos)),
+          'P' | 'p' => match match_meridiem(s, pos) {
             Some(item) => { let (v, pos) = item; tm.tm_hour += v; Ok(pos) }
-            None => Err(~"Invalid hour")
-          },
-          'p' => match match_strs(s, pos,
-                                  [(~"AM", 0_i32), (~"PM", 12_i32)]) {
-
-            Some(item) => { let (v, pos) = item; tm.tm_hour += v; Ok(pos) }
-            None => Err(~"Invalid hour")
+            None => Err(error_at(s, pos, "Invalid hour"))
           },
           'R' => {
-            parse_type(s, pos, 'H', &mut *tm)
+            parse_type(s, pos, 'H', &mut *tm, lenient)
                 .and_then(|pos| parse_char(s, pos, ':'))
-                .and_then(|pos| parse_type(s, pos, 'M', &mut *tm))
+                .and_then(|pos| parse_type(s, pos, 'M', &mut *tm, lenient))
           }
           'r' => {
-            parse_type(s, pos, 'I', &mut *tm)
+            parse_type(s, pos, 'I', &mut *tm, lenient)
                 .and_then(|pos| parse_char(s, pos, ':'))
-                .and_then(|pos| parse_type(s, pos, 'M', &mut *tm))
+                .and_then(|pos| parse_type(s, pos, 'M', &mut *tm, lenient))
                 .and_then(|pos| parse_char(s, pos, ':'))
-                .and_then(|pos| parse_type(s, pos, 'S', &mut *tm))
+                .and_then(|pos| parse_type(s, pos, 'S', &mut *tm, lenient))
                 .and_then(|pos| parse_char(s, pos, ' '))
-                .and_then(|pos| parse_type(s, pos, 'p', &mut *tm))
+                .and_then(|pos| parse_type(s, pos, 'p', &mut *tm, lenient))
           }
           'S' => {
-            match match_digits_in_range(s, pos, 2u, false, 0_i32, 60_i32) {
+            match match_digits_in_range(s, pos, 2u, false, lenient, 0_i32, 60_i32) {
               Some(item) => {
                 let (v, pos) = item;
                 tm.tm_sec = v;
                 Ok(pos)
               }
-              None => Err(~"Invalid second")
+              None => Err(error_at(s, pos, "Invalid second"))
             }
           }
           //'s' {}
           'T' | 'X' => {
-            parse_type(s, pos, 'H', &mut *tm)
+            parse_type(s, pos, 'H', &mut *tm, lenient)
                 .and_then(|pos| parse_char(s, pos, ':'))
-                .and_then(|pos| parse_type(s, pos, 'M', &mut *tm))
+                .and_then(|pos| parse_type(s, pos, 'M', &mut *tm, lenient))
                 .and_then(|pos| parse_char(s, pos, ':'))
-                .and_then(|pos| parse_type(s, pos, 'S', &mut *tm))
+                .and_then(|pos| parse_type(s, pos, 'S', &mut *tm, lenient))
           }
-          't' => parse_char(s, pos, '\t'),
+          't' => Ok(skip_whitespace(s, pos)),
           'u' => {
-            match match_digits_in_range(s, pos, 1u, false, 1_i32, 7_i32) {
+            match match_digits_in_range(s, pos, 1u, false, lenient, 1_i32, 7_i32) {
               Some(item) => {
                 let (v, pos) = item;
                 tm.tm_wday = if v == 7 { 0 } else { v };
                 Ok(pos)
               }
-              None => Err(~"Invalid day of week")
+              None => Err(error_at(s, pos, "Invalid day of week"))
             }
           }
           'v' => {
-            parse_type(s, pos, 'e', &mut *tm)
+            parse_type(s, pos, 'e', &mut *tm, lenient)
                 .and_then(|pos|  parse_char(s, pos, '-'))
-                .and_then(|pos| parse_type(s, pos, 'b', &mut *tm))
+                .and_then(|pos| parse_type(s, pos, 'b', &mut *tm, lenient))
                 .and_then(|pos| parse_char(s, pos, '-'))
-                .and_then(|pos| parse_type(s, pos, 'Y', &mut *tm))
+                .and_then(|pos| parse_type(s, pos, 'Y', &mut *tm, lenient))
           }
           //'W' {}
           'w' => {
-            match match_digits_in_range(s, pos, 1u, false, 0_i32, 6_i32) {
+            match match_digits_in_range(s, pos, 1u, false, lenient, 0_i32, 6_i32) {
               Some(item) => { let (v, pos) = item; tm.tm_wday = v; Ok(pos) }
-              None => Err(~"Invalid day of week")
+              None => Err(error_at(s, pos, "Invalid day of week"))
             }
           }
           //'X' {}
           //'x' {}
           'Y' => {
-            match match_digits(s, pos, 4u, false) {
+            match match_digits(s, pos, 4u, false, false) {
               Some(item) => {
                 let (v, pos) = item;
                 tm.tm_year = v - 1900_i32;
                 Ok(pos)
               }
-              None => Err(~"Invalid year")
+              None => Err(error_at(s, pos, "Invalid year"))
             }
           }
           'y' => {
-            match match_digits_in_range(s, pos, 2u, false, 0_i32, 99_i32) {
+            match match_digits_in_range(s, pos, 2u, false, lenient, 0_i32, 99_i32) {
               Some(item) => {
                 let (v, pos) = item;
-                tm.tm_year = v;
+                // POSIX pivot: 69-99 are 1969-1999, 00-68 are 2000-2068.
+                tm.tm_year = if v < 69_i32 { v + 100_i32 } else { v };
                 Ok(pos)
               }
-              None => Err(~"Invalid year")
+              None => Err(error_at(s, pos, "Invalid year"))
             }
           }
           'Z' => {
@@ -653,7 +1729,7 @@ fn do_strptime(s: &str, format: &str) -> Result<Tm, ~str> {
             let range = s.char_range_at(pos);
 
             if range.ch == '+' || range.ch == '-' {
-                match match_digits(s, range.next, 4u, false) {
+                match match_digits(s, range.next, 4u, false, false) {
                   Some(item) => {
                     let (v, pos) = item;
                     if v == 0_i32 {
@@ -663,10 +1739,10 @@ fn do_strptime(s: &str, format: &str) -> Result<Tm, ~str> {
 
                     Ok(pos)
                   }
-                  None => Err(~"Invalid zone offset")
+                  None => Err(error_at(s, pos, "Invalid zone offset"))
                 }
             } else {
-                Err(~"Invalid zone offset")
+                Err(error_at(s, pos, "Invalid zone offset"))
             }
           }
           '%' => parse_char(s, pos, '%'),
@@ -686,14 +1762,16 @@ fn do_strptime(s: &str, format: &str) -> Result<Tm, ~str> {
             tm_year: 0_i32,
             tm_wday: 0_i32,
             tm_yday: 0_i32,
-            tm_isdst: 0_i32,
+            tm_isdst: -1_i32,
             tm_gmtoff: 0_i32,
             tm_zone: ~"",
             tm_nsec: 0_i32,
         };
         let mut pos = 0u;
         let len = s.len();
-        let mut result = Err(~"Invalid time");
+        let mut error = None;
+        let mut has_yday = false;
+        let mut has_year = false;
 
         while !rdr.eof() && pos < len {
             let range = s.char_range_at(pos);
@@ -702,19 +1780,60 @@ fn do_strptime(s: &str, format: &str) -> Result<Tm, ~str> {
 
             match rdr.read_char() {
                 '%' => {
-                    match parse_type(s, pos, rdr.read_char(), &mut tm) {
+                    let spec = rdr.read_char();
+                    match spec {
+                        'j' => has_yday = true,
+                        'Y' | 'y' | 'c' | 'D' | 'x' | 'F' | 'v' => has_year = true,
+                        _ => ()
+                    }
+                    match parse_type(s, pos, spec, &mut tm, lenient) {
                         Ok(next) => pos = next,
-                        Err(e) => { result = Err(e); break; }
+                        Err(e) => { error = Some(e); break; }
                     }
                 },
                 c => {
-                    if c != ch { break }
+                    if c != ch {
+                        error = Some(error_at(s, pos, format!("Expected '{}'", c)));
+                        break;
+                    }
                     pos = next;
                 }
             }
         }
 
-        if pos == len && rdr.eof() {
+        // A year and a day of the year without a day of the month, as in the
+        // ordinal date "2009-044", also determine the month and day.
+        let has_yday = has_yday && has_year;
+        let year = tm.tm_year as i64 + 1900;
+        let days_in_year = if is_leap_year(year) { 366 } else { 365 };
+        if has_yday && tm.tm_mday == 0_i32 && tm.tm_yday as i64 < days_in_year {
+            let days = days_from_civil(year, 0, 1) + tm.tm_yday as i64;
+            let date = at_utc_pure(Timespec::new(days * 86400, 0));
+            tm.tm_mon = date.tm_mon;
+            tm.tm_mday = date.tm_mday;
+        }
+
+        // `%n` and `%t` match no input at all, so they may be left over
+        // once the input is used up.
+        let mut rest = format.slice_from(rdr.tell());
+        if pos == len {
+            while rest.starts_with("%n") || rest.starts_with("%t") {
+                rest = rest.slice_from(2);
+            }
+        }
+
+        if error.is_some() {
+            Err(error.unwrap())
+        } else if has_yday && tm.tm_yday as i64 >= days_in_year {
+            Err(format!("Invalid day of year for {}: {}", year, tm.tm_yday + 1))
+        } else if pos < len {
+            Err(error_at(s, pos, "Unexpected trailing input"))
+        } else if !rest.is_empty() {
+            // Name the directive or literal character that was expected.
+            let skip = if rest.starts_with("%") && rest.len() > 1 { 1 } else { 0 };
+            let expected = rest.slice_to(rest.char_range_at(skip).next);
+            Err(format!("Input ended at position {}, expected '{}'", pos, expected))
+        } else {
             Ok(Tm {
                 tm_sec: tm.tm_sec,
                 tm_min: tm.tm_min,
@@ -729,12 +1848,62 @@ fn do_strptime(s: &str, format: &str) -> Result<Tm, ~str> {
                 tm_zone: tm.tm_zone.clone(),
                 tm_nsec: tm.tm_nsec,
             })
-        } else { result }
+        }
     }
 }
 
-fn do_strftime(format: &str, tm: &Tm) -> ~str {
-    fn parse_type(ch: char, tm: &Tm) -> ~str {
+fn do_strftime(format: &str, tm: &TmRef) -> ~str {
+    // The hour on a 12-hour clock, shared by `%I` and `%l` so that midnight
+    // and noon both show as 12.
+    fn hour_12(tm: &TmRef) -> i32 {
+        match tm.tm_hour {
+            0 => 12,
+            h if h > 12 => h - 12,
+            h => h
+        }
+    }
+
+    // Week and day-of-year numbers, worked out once per call so a format
+    // that uses several of `%G`, `%g`, `%j`, `%U`, `%V` and `%W` only does
+    // the arithmetic once.
+    struct DateContext {
+        yday: i32,
+        sunday_week: i32,
+        monday_week: i32,
+        iso_year: i64,
+        iso_week: i32
+    }
+
+    fn date_context(tm: &TmRef) -> DateContext {
+        // Number of ISO weeks in `year`, given the weekday (Monday = 0) of
+        // its January 1st.
+        fn iso_weeks(year: i64, jan1: i32) -> i32 {
+            if jan1 == 3 || (jan1 == 2 && is_leap_year(year)) { 53 } else { 52 }
+        }
+
+        let year = tm.tm_year as i64 + 1900;
+        let mon_wday = (tm.tm_wday + 6) % 7;
+        let jan1 = ((mon_wday - tm.tm_yday) % 7 + 7) % 7;
+        let week = (tm.tm_yday - mon_wday + 10) / 7;
+        let (iso_year, iso_week) = if week < 1 {
+            let days = if is_leap_year(year - 1) { 366 } else { 365 };
+            (year - 1, iso_weeks(year - 1, ((jan1 - days) % 7 + 7) % 7))
+        } else if week > iso_weeks(year, jan1) {
+            (year + 1, 1)
+        } else {
+            (year, week)
+        };
+
+        DateContext {
+            yday: tm.tm_yday,
+            sunday_week: (tm.tm_yday + 7 - tm.tm_wday) / 7,
+            monday_week: (tm.tm_yday + 7 - mon_wday) / 7,
+            iso_year: iso_year,
+            iso_week: iso_week
+        }
+    }
+
+    fn parse_type(ch: char, tm: &TmRef, ctx: &DateContext) -> ~str {
         //FIXME (#2350): Implement missing types.
       let die = || format!("strftime: can't understand this format {} ", ch);
         match ch {
@@ -791,44 +1960,34 @@ fn do_strftime(format: &str, tm: &Tm) -> ~str {
           'C' => format!("{:02d}", (tm.tm_year as int + 1900) / 100),
           'c' => {
             format!("{} {} {} {} {}",
-                parse_type('a', tm),
-                parse_type('b', tm),
-                parse_type('e', tm),
-                parse_type('T', tm),
-                parse_type('Y', tm))
+                parse_type('a', tm, ctx),
+                parse_type('b', tm, ctx),
+                parse_type('e', tm, ctx),
+                parse_type('T', tm, ctx),
+                parse_type('Y', tm, ctx))
           }
           'D' | 'x' => {
             format!("{}/{}/{}",
-                parse_type('m', tm),
-                parse_type('d', tm),
-                parse_type('y', tm))
+                parse_type('m', tm, ctx),
+                parse_type('d', tm, ctx),
+                parse_type('y', tm, ctx))
           }
           'd' => format!("{:02d}", tm.tm_mday),
           'e' => format!("{:2d}", tm.tm_mday),
-          'f' => format!("{:09d}", tm.tm_nsec),
+          'f' | 'N' => format!("{:09d}", tm.tm_nsec),
           'F' => {
             format!("{}-{}-{}",
-                parse_type('Y', tm),
-                parse_type('m', tm),
-                parse_type('d', tm))
+                parse_type('Y', tm, ctx),
+                parse_type('m', tm, ctx),
+                parse_type('d', tm, ctx))
           }
-          //'G' {}
-          //'g' {}
+          'G' => ctx.iso_year.to_str(),
+          'g' => format!("{:02d}", (ctx.iso_year % 100 + 100) % 100),
           'H' => format!("{:02d}", tm.tm_hour),
-          'I' => {
-            let mut h = tm.tm_hour;
-            if h == 0 { h = 12 }
-            if h > 12 { h -= 12 }
-            format!("{:02d}", h)
-          }
-          'j' => format!("{:03d}", tm.tm_yday + 1),
+          'I' => format!("{:02d}", hour_12(tm)),
+          'j' => format!("{:03d}", ctx.yday + 1),
           'k' => format!("{:2d}", tm.tm_hour),
-          'l' => {
-            let mut h = tm.tm_hour;
-            if h == 0 { h = 12 }
-            if h > 12 { h -= 12 }
-            format!("{:2d}", h)
-          }
+          'l' => format!("{:2d}", hour_12(tm)),
           'M' => format!("{:02d}", tm.tm_min),
           'm' => format!("{:02d}", tm.tm_mon + 1),
           'n' => ~"\n",
@@ -836,44 +1995,44 @@ fn do_strftime(format: &str, tm: &Tm) -> ~str {
           'p' => if (tm.tm_hour as int) < 12 { ~"AM" } else { ~"PM" },
           'R' => {
             format!("{}:{}",
-                parse_type('H', tm),
-                parse_type('M', tm))
+                parse_type('H', tm, ctx),
+                parse_type('M', tm, ctx))
           }
           'r' => {
             format!("{}:{}:{} {}",
-                parse_type('I', tm),
-                parse_type('M', tm),
-                parse_type('S', tm),
-                parse_type('p', tm))
+                parse_type('I', tm, ctx),
+                parse_type('M', tm, ctx),
+                parse_type('S', tm, ctx),
+                parse_type('p', tm, ctx))
           }
           'S' => format!("{:02d}", tm.tm_sec),
-          's' => format!("{}", tm.to_timespec().sec),
+          's' => format!("{}", tm.to_tm().to_timespec().sec),
           'T' | 'X' => {
             format!("{}:{}:{}",
-                parse_type('H', tm),
-                parse_type('M', tm),
-                parse_type('S', tm))
+                parse_type('H', tm, ctx),
+                parse_type('M', tm, ctx),
+                parse_type('S', tm, ctx))
           }
           't' => ~"\t",
-          //'U' {}
+          'U' => format!("{:02d}", ctx.sunday_week),
           'u' => {
             let i = tm.tm_wday as int;
             (if i == 0 { 7 } else { i }).to_str()
           }
-          //'V' {}
+          'V' => format!("{:02d}", ctx.iso_week),
           'v' => {
             format!("{}-{}-{}",
-                parse_type('e', tm),
-                parse_type('b', tm),
-                parse_type('Y', tm))
+                parse_type('e', tm, ctx),
+                parse_type('b', tm, ctx),
+                parse_type('Y', tm, ctx))
           }
-          //'W' {}
+          'W' => format!("{:02d}", ctx.monday_week),
           'w' => (tm.tm_wday as int).to_str(),
           //'X' {}
           //'x' {}
           'Y' => (tm.tm_year as int + 1900).to_str(),
           'y' => format!("{:02d}", (tm.tm_year as int + 1900) % 100),
-          'Z' => tm.tm_zone.clone(),
+          'Z' => tm.tm_zone.to_owned(),
           'z' => {
             let sign = if tm.tm_gmtoff > 0_i32 { '+' } else { '-' };
             let mut m = num::abs(tm.tm_gmtoff) / 60_i32;
@@ -887,12 +2046,33 @@ fn do_strftime(format: &str, tm: &Tm) -> ~str {
         }
     }
 
+    // Drops the zero or space padding from a numeric field, as the GNU
+    // `-` flag (e.g. `%-d`) does. Other fields are left alone.
+    fn parse_type_unpadded(ch: char, tm: &TmRef, ctx: &DateContext) -> ~str {
+        let s = parse_type(ch, tm, ctx);
+        match ch {
+          'C' | 'd' | 'e' | 'g' | 'H' | 'I' | 'j' | 'k' | 'l' | 'M' | 'm' | 'S' |
+          'U' | 'V' | 'W' | 'y' => {
+            let mut i = 0u;
+            while i + 1 < s.len() && (s[i] == '0' as u8 || s[i] == ' ' as u8) {
+                i += 1;
+            }
+            s.slice_from(i).to_owned()
+          }
+          _ => s
+        }
+    }
+
+    let ctx = date_context(tm);
     let mut buf = ~"";
 
     do io::with_str_reader(format) |rdr| {
         while !rdr.eof() {
             match rdr.read_char() {
-                '%' => buf.push_str(parse_type(rdr.read_char(), tm)),
+                '%' => match rdr.read_char() {
+                    '-' => buf.push_str(parse_type_unpadded(rdr.read_char(), tm, &ctx)),
+                    ch => buf.push_str(parse_type(ch, tm, &ctx))
+                },
                 ch => buf.push_char(ch)
             }
         }
@@ -906,8 +2086,11 @@ mod tests {
     use super::*;
 
     use std::f64;
+    use std::i64;
     use std::os;
+    use std::rand;
     use std::result::{Err, Ok};
+    use test::BenchHarness;
 
     fn test_get_time() {
         static SOME_RECENT_DATE: i64 = 1325376000i64; // 2012-01-01T00:00:00Z
@@ -968,6 +2151,51 @@ mod tests {
         assert!(utc.tm_nsec == 54321_i32);
     }
 
+    fn test_at_utc_pure() {
+        os::setenv("TZ", "America/Los_Angeles");
+        tzset();
+
+        let times = [1234567890, 0, -1, -86401, 951782400, -123456789,
+                     -2208988801, 4102444800];
+        for &sec in times.iter() {
+            let time = Timespec::new(sec, 54321);
+            assert_eq!(at_utc_pure(time), at_utc(time));
+        }
+    }
+
+    fn test_to_iso8601_utc() {
+        assert_eq!(Timespec::new(1234567890, 54321).to_iso8601_utc(),
+                   ~"2009-02-13T23:31:30Z");
+        assert_eq!(Timespec::new(951782400, 0).to_iso8601_utc(),
+                   ~"2000-02-29T00:00:00Z");
+        assert_eq!(Timespec::new(-1, 0).to_iso8601_utc(),
+                   ~"1969-12-31T23:59:59Z");
+        assert_eq!(Timespec::new(-123456789, 0).to_iso8601_utc(),
+                   ~"1966-02-02T02:26:51Z");
+        assert_eq!(Timespec::new(-2208988801, 0).to_iso8601_utc(),
+                   ~"1899-12-31T23:59:59Z");
+    }
+
+    fn test_from_iso8601() {
+        let times = [Timespec::new(1234567890, 0), Timespec::new(0, 0),
+                     Timespec::new(-1, 0), Timespec::new(-123456789, 0),
+                     Timespec::new(951782400, 0)];
+        for ts in times.iter() {
+            assert_eq!(Timespec::from_iso8601(ts.to_iso8601_utc()), Ok(*ts));
+        }
+
+        let ts = Timespec::new(1234567890, 0);
+        assert_eq!(Timespec::from_iso8601("2009-02-13T15:31:30-08:00"), Ok(ts));
+        assert_eq!(Timespec::from_iso8601("2009-02-14T05:01:30+05:30"), Ok(ts));
+        assert_eq!(Timespec::from_iso8601("2009-02-13T23:31:30.25Z"),
+                   Ok(Timespec::new(1234567890, 250_000_000)));
+
+        assert!(Timespec::from_iso8601("2009-02-13T23:31:30").is_err());
+        assert!(Timespec::from_iso8601("2009-02-13T23:31:30*08:00").is_err());
+        assert!(Timespec::from_iso8601("2009-02-13 23:31:30Z").is_err());
+        assert!(Timespec::from_iso8601("2009-02-13T23:31Z").is_err());
+    }
+
     fn test_at() {
         os::setenv("TZ", "America/Los_Angeles");
         tzset();
@@ -1007,6 +2235,165 @@ mod tests {
         assert_eq!(utc.to_local().to_timespec(), time);
     }
 
+    fn test_with_dst() {
+        os::setenv("TZ", "America/Los_Angeles");
+        tzset();
+
+        // 2009-03-08 03:30:00 PDT, half an hour after clocks sprang forward
+        let time = Timespec::new(1236508200, 0);
+        let local = at(time);
+        assert_eq!(local.tm_isdst, 1);
+        assert_eq!(local.tm_hour, 3);
+
+        assert_eq!(local.with_dst(1).to_timespec(), time);
+        assert_eq!(local.with_dst(-1).to_timespec(), time);
+        // Claiming standard time reads 03:30 as PST, an hour later
+        assert_eq!(local.with_dst(0).to_timespec(), Timespec::new(1236511800, 0));
+
+        assert_eq!(local.with_dst(-1).tm_isdst, -1);
+        assert!(local.with_dst(1) == local);
+    }
+
+    fn test_field_cmp() {
+        os::setenv("TZ", "America/Los_Angeles");
+        tzset();
+
+        // The same instant, shown in Los Angeles and in UTC.
+        let time = Timespec::new(1234567890, 54321);
+        let local = at(time);
+        let utc = at_utc(time);
+        assert_eq!(local.to_timespec(), utc.to_timespec());
+        assert_eq!(local.field_cmp(&utc), Less);
+        assert_eq!(utc.field_cmp(&local), Greater);
+        assert_eq!(local.field_cmp(&local.clone()), Equal);
+
+        // Fields are compared from year down to nanoseconds.
+        let later = at_utc(Timespec::new(1234567890, 54322));
+        assert_eq!(utc.field_cmp(&later), Less);
+        let mut next_year = utc.clone();
+        next_year.tm_year += 1;
+        next_year.tm_mon = 0;
+        assert_eq!(utc.field_cmp(&next_year), Less);
+    }
+
+    fn test_instant() {
+        use sort;
+
+        os::setenv("TZ", "America/Los_Angeles");
+        tzset();
+
+        // 10:00 in Los Angeles is after 11:00 UTC the same day.
+        let la_10 = at(Timespec::new(1234548000, 0));  // 2009-02-13T18:00:00Z
+        let utc_11 = at_utc(Timespec::new(1234522800, 0));
+        let utc_18 = at_utc(Timespec::new(1234548000, 0));
+        let utc_12 = at_utc(Timespec::new(1234526400, 0));
+        assert_eq!(la_10.tm_hour, 10_i32);
+        assert!(la_10 != utc_18);
+        assert!(Instant(la_10.clone()) == Instant(utc_18.clone()));
+        assert!(Instant(utc_11.clone()) < Instant(la_10.clone()));
+        assert_eq!(Instant(la_10.clone()).cmp(&Instant(utc_18.clone())), Equal);
+
+        let mut times = ~[Instant(la_10.clone()), Instant(utc_12.clone()),
+                          Instant(utc_11.clone())];
+        sort::tim_sort(times);
+        let sorted: ~[Timespec] = times.iter().map(|t| t.to_timespec()).collect();
+        assert_eq!(sorted, ~[utc_11.to_timespec(), utc_12.to_timespec(),
+                             la_10.to_timespec()]);
+    }
+
+    fn test_new_normalized() {
+        assert_eq!(Timespec::new_normalized(10, -1), Timespec::new(9, 999_999_999));
+        assert_eq!(Timespec::new_normalized(10, 2_000_000_000), Timespec::new(12, 0));
+        assert_eq!(Timespec::new_normalized(10, 2_000_000_001), Timespec::new(12, 1));
+        assert_eq!(Timespec::new_normalized(10, -2_000_000_000), Timespec::new(8, 0));
+        assert_eq!(Timespec::new_normalized(10, -2_000_000_001),
+                   Timespec::new(7, 999_999_999));
+        assert_eq!(Timespec::new_normalized(-1, 500), Timespec::new(-1, 500));
+        assert_eq!(Timespec::new_normalized(0, 0), Timespec::new(0, 0));
+    }
+
+    fn test_approx_eq() {
+        let a = Timespec::new(1234567890, 999_800_000);
+        let b = Timespec::new(1234567891, 300_000);
+        // 500us apart, across a second boundary.
+        assert!(a.approx_eq(&b, 500_000));
+        assert!(b.approx_eq(&a, 500_000));
+        assert!(a.approx_eq(&b, 1_000_000));
+        assert!(!a.approx_eq(&b, 499_999));
+        assert!(!b.approx_eq(&a, 499_999));
+
+        assert!(a.approx_eq(&a, 0));
+        assert!(!a.approx_eq(&a, -1));
+
+        let min = Timespec::new(i64::min_value, 0);
+        let max = Timespec::new(i64::max_value, 0);
+        assert!(!min.approx_eq(&max, i64::max_value));
+        assert!(max.approx_eq(&max, 0));
+    }
+
+    fn test_round_to() {
+        os::setenv("TZ", "America/Los_Angeles");
+        tzset();
+
+        static MINUTE: u64 = 60 * 1_000_000_000;
+
+        // 2009-02-13 23:31:30 UTC rounds down to 23:30...
+        let tm = at_utc(Timespec::new(1234567890, 54321)).round_to(15 * MINUTE);
+        assert_eq!(tm.strftime("%F %T.%f"), ~"2009-02-13 23:30:00.000000000");
+        // ... while 23:38:00 rounds up to 23:45, as does the halfway point.
+        let tm = at_utc(Timespec::new(1234568280, 0)).round_to(15 * MINUTE);
+        assert_eq!(tm.strftime("%F %T"), ~"2009-02-13 23:45:00");
+        let tm = at_utc(Timespec::new(1234568250, 0)).round_to(15 * MINUTE);
+        assert_eq!(tm.strftime("%F %T"), ~"2009-02-13 23:45:00");
+        let tm = at_utc(Timespec::new(1234568249, 999999999)).round_to(15 * MINUTE);
+        assert_eq!(tm.strftime("%F %T"), ~"2009-02-13 23:30:00");
+
+        // Local times stay local.
+        let tm = at(Timespec::new(1234567890, 0)).round_to(15 * MINUTE);
+        assert_eq!(tm.strftime("%F %T %z"), ~"2009-02-13 15:30:00 -0800");
+
+        // A fixed offset other than TZ's is kept: 2009-02-14 05:01:30 +0530
+        // is 23:31:30 UTC, which rounds down to 23:30 UTC.
+        let ist = FixedOffset::new(5 * 3600 + 30 * 60, "IST");
+        let tm = at_zone(Timespec::new(1234567890, 0), &ist).round_to(15 * MINUTE);
+        assert_eq!(tm.strftime("%F %T %z %Z"), ~"2009-02-14 05:00:00 +0530 IST");
+        assert_eq!(tm.round_to(1), tm);
+        let tm = strptime("2009-02-13 20:40:00 -0300", "%F %T %z").unwrap();
+        assert_eq!(tm.round_to(60 * MINUTE).strftime("%F %T %z"),
+                   ~"2009-02-13 21:00:00 -0300");
+
+        // Steps that don't divide an hour, and sub-second steps.
+        assert_eq!(Timespec::new(1234567890, 0).round_to(7 * MINUTE),
+                   Timespec::new(1234567740, 0));
+        assert_eq!(Timespec::new(10, 250_000_000).round_to(500_000_000),
+                   Timespec::new(10, 500_000_000));
+
+        // Pre-epoch times round towards the nearest multiple too.
+        assert_eq!(Timespec::new(-1, 0).round_to(MINUTE), Timespec::new(0, 0));
+        assert_eq!(Timespec::new(-31, 0).round_to(MINUTE), Timespec::new(-60, 0));
+        assert_eq!(Timespec::new(-2, 800_000_000).round_to(1_000_000_000),
+                   Timespec::new(-1, 0));
+    }
+
+    fn test_whole_years_until() {
+        let date = |s: &str| strptime(s, "%F").unwrap();
+        let birthday = date("1980-06-15");
+
+        assert_eq!(birthday.whole_years_until(&date("2009-06-14")), 28);
+        assert_eq!(birthday.whole_years_until(&date("2009-06-15")), 29);
+        assert_eq!(birthday.whole_years_until(&date("2009-12-31")), 29);
+        assert_eq!(birthday.whole_years_until(&date("2010-01-01")), 29);
+        assert_eq!(birthday.whole_years_until(&birthday), 0);
+        assert_eq!(birthday.whole_years_until(&date("1980-06-14")), -1);
+
+        // Leap day birthdays are celebrated on February 28th otherwise.
+        let leap_day = date("2008-02-29");
+        assert_eq!(leap_day.whole_years_until(&date("2009-02-27")), 0);
+        assert_eq!(leap_day.whole_years_until(&date("2009-02-28")), 1);
+        assert_eq!(leap_day.whole_years_until(&date("2012-02-28")), 3);
+        assert_eq!(leap_day.whole_years_until(&date("2012-02-29")), 4);
+    }
+
     fn test_conversions() {
         os::setenv("TZ", "America/Los_Angeles");
         tzset();
@@ -1036,7 +2423,7 @@ mod tests {
             assert!(tm.tm_mon == 0_i32);
             assert!(tm.tm_year == 0_i32);
             assert!(tm.tm_wday == 0_i32);
-            assert!(tm.tm_isdst == 0_i32);
+            assert!(tm.tm_isdst == -1_i32);
             assert!(tm.tm_gmtoff == 0_i32);
             assert!(tm.tm_zone == ~"");
             assert!(tm.tm_nsec == 0_i32);
@@ -1045,9 +2432,12 @@ mod tests {
         }
 
         let format = "%a %b %e %T.%f %Y";
-        assert_eq!(strptime("", format), Err(~"Invalid time"));
-        assert!(strptime("Fri Feb 13 15:31:30", format)
-            == Err(~"Invalid time"));
+        assert_eq!(strptime("", format),
+                   Err(~"Input ended at position 0, expected '%a'"));
+        assert_eq!(strptime("Fri Feb 13 15:31:30", format),
+                   Err(~"Input ended at position 19, expected '.'"));
+        assert_eq!(strptime("Fri Feb 13 15:31:30.01234 2009 PST", format),
+                   Err(~"Unexpected trailing input at position 30: ' '"));
 
         match strptime("Fri Feb 13 15:31:30.01234 2009", format) {
           Err(e) => fail2!(e),
@@ -1060,7 +2450,7 @@ mod tests {
             assert!(tm.tm_year == 109_i32);
             assert!(tm.tm_wday == 5_i32);
             assert!(tm.tm_yday == 0_i32);
-            assert!(tm.tm_isdst == 0_i32);
+            assert!(tm.tm_isdst == -1_i32);
             assert!(tm.tm_gmtoff == 0_i32);
             assert!(tm.tm_zone == ~"");
             assert!(tm.tm_nsec == 12340000_i32);
@@ -1160,6 +2550,19 @@ mod tests {
         assert!(test("pm", "%P"));
         assert!(test("AM", "%p"));
         assert!(test("PM", "%p"));
+
+        // AM/PM is matched case-insensitively and may be dotted.
+        let hour = |s: &str, format: &str| strptime(s, format).unwrap().tm_hour;
+        assert_eq!(hour("3 a.m.", "%l %p"), 3_i32);
+        assert_eq!(hour("3 Pm", "%l %p"), 15_i32);
+        assert_eq!(hour("3 p.m.", "%l %P"), 15_i32);
+        assert_eq!(hour("3 AM", "%l %P"), 3_i32);
+        assert_eq!(hour("3 p.m", "%l %p"), 15_i32);
+        assert!(strptime("3 pm.", "%l %p").is_err());
+        assert!(strptime("3 xm", "%l %p").is_err());
+        assert!(strptime("3 p", "%l %p").is_err());
+        assert_eq!(strptime("3 p.m.", "%l %p").unwrap().strftime("%l %p %P"),
+                   ~" 3 PM pm");
         assert!(test("23:31", "%R"));
         assert!(test("11:31:30 AM", "%r"));
         assert!(test("11:31:30 PM", "%r"));
@@ -1174,6 +2577,26 @@ mod tests {
         assert!(test("6", "%w"));
         assert!(test("2009", "%Y"));
         assert!(test("09", "%y"));
+
+        // Ordinal dates fill in the month and day, including leap days.
+        let tm = strptime("2008-366", "%Y-%j").unwrap();
+        assert_eq!((tm.tm_mon, tm.tm_mday, tm.tm_yday), (11_i32, 31_i32, 365_i32));
+        assert_eq!(tm.strftime("%Y-%j"), ~"2008-366");
+        assert_eq!(tm.strftime("%F"), ~"2008-12-31");
+        assert_eq!(strptime("2008-060", "%Y-%j").unwrap().strftime("%F"), ~"2008-02-29");
+        assert_eq!(strptime("2009-060", "%Y-%j").unwrap().strftime("%F"), ~"2009-03-01");
+        assert_eq!(strptime("2009-044", "%Y-%j").unwrap().strftime("%F"), ~"2009-02-13");
+        assert_eq!(strptime("2009-001", "%Y-%j").unwrap().strftime("%F"), ~"2009-01-01");
+        assert!(strptime("2009-366", "%Y-%j").is_err());
+        // An explicit day of the month is kept as parsed.
+        assert_eq!(strptime("2009-044 03/01", "%Y-%j %m/%d").unwrap().strftime("%F"),
+                   ~"2009-03-01");
+        assert_eq!(strptime("09", "%y").unwrap().tm_year, 109_i32);
+        assert_eq!(strptime("00", "%y").unwrap().tm_year, 100_i32);
+        assert_eq!(strptime("68", "%y").unwrap().tm_year, 168_i32);
+        assert_eq!(strptime("69", "%y").unwrap().tm_year, 69_i32);
+        assert_eq!(strptime("99", "%y").unwrap().tm_year, 99_i32);
+        assert_eq!(strptime("02/13/09", "%D").unwrap().tm_year, 109_i32);
         assert!(strptime("UTC", "%Z").unwrap().tm_zone ==
             ~"UTC");
         assert!(strptime("PST", "%Z").unwrap().tm_zone ==
@@ -1185,7 +2608,136 @@ mod tests {
         assert!(test("%", "%%"));
 
         // Test for #7256
-        assert_eq!(strptime("360", "%Y-%m-%d"), Err(~"Invalid year"))
+        assert_eq!(strptime("360", "%Y-%m-%d"),
+                   Err(~"Invalid year at position 0: '3'"));
+
+        assert_eq!(strptime("2009-1x-13", "%Y-%m-%d"),
+                   Err(~"Invalid month at position 5: '1'"));
+        assert_eq!(strptime("2009/02/13", "%Y-%m-%d"),
+                   Err(~"Expected '-' at position 4: '/'"));
+        assert_eq!(strptime("2009-02-13x", "%Y-%m-%d"),
+                   Err(~"Unexpected trailing input at position 10: 'x'"));
+        assert_eq!(strptime("2009-02", "%Y-%m-%d"),
+                   Err(~"Input ended at position 7, expected '-'"));
+        assert_eq!(strptime("Fri Feb 13 15:31:30 2009", "%c %Z"),
+                   Err(~"Input ended at position 24, expected ' '"));
+        assert_eq!(strptime("13 Fbe 2009", "%d %b %Y"),
+                   Err(~"Invalid month at position 3: 'F'"));
+    }
+
+    fn test_strptime_lenient() {
+        let tm = strptime_lenient("2009-2-3", "%Y-%m-%d").unwrap();
+        assert_eq!(tm.tm_year, 109_i32);
+        assert_eq!(tm.tm_mon, 1_i32);
+        assert_eq!(tm.tm_mday, 3_i32);
+        assert!(strptime("2009-2-3", "%Y-%m-%d").is_err());
+
+        let tm = strptime_lenient("2009-02-13 9:05:7", "%Y-%m-%d %H:%M:%S").unwrap();
+        assert_eq!(tm.tm_mday, 13_i32);
+        assert_eq!(tm.tm_hour, 9_i32);
+        assert_eq!(tm.tm_min, 5_i32);
+        assert_eq!(tm.tm_sec, 7_i32);
+
+        // Fields still read greedily up to their full width.
+        let tm = strptime_lenient("12/31/99", "%m/%d/%y").unwrap();
+        assert_eq!(tm.tm_mon, 11_i32);
+        assert_eq!(tm.tm_mday, 31_i32);
+        assert!(strptime_lenient("2009-13-1", "%Y-%m-%d").is_err());
+        assert!(strptime_lenient("2009--1", "%Y-%m-%d").is_err());
+        assert!(strptime_lenient("209-1-1", "%Y-%m-%d").is_err());
+
+        // Space-padded fields take one digit followed by a separator space,
+        // as well as their padded form.
+        let tm = strptime_lenient("3 Feb 2009", "%e %b %Y").unwrap();
+        assert_eq!(tm.tm_mday, 3_i32);
+        assert_eq!(tm.tm_year, 109_i32);
+        assert_eq!(strptime_lenient(" 3 Feb 2009", "%e %b %Y").unwrap().tm_mday, 3_i32);
+        let tm = strptime_lenient("7 05", "%k %M").unwrap();
+        assert_eq!(tm.tm_hour, 7_i32);
+        assert_eq!(tm.tm_min, 5_i32);
+        assert_eq!(strptime_lenient("9 PM", "%l %p").unwrap().tm_hour, 21_i32);
+        assert_eq!(strptime_lenient("12 AM", "%l %p").unwrap().tm_hour, 0_i32);
+    }
+
+    fn test_strptime_strict() {
+        let s = "Mon, 13 Feb 2009";
+        let format = "%a, %d %b %Y";
+        assert!(strptime(s, format).is_ok());
+        assert!(strptime_strict(s, format).is_err());
+
+        let tm = strptime_strict("Fri, 13 Feb 2009", format).unwrap();
+        assert_eq!(tm.tm_wday, 5_i32);
+        assert!(strptime_strict("Friday 2009-02-13", "%A %F").is_ok());
+        assert!(strptime_strict("Thursday 2009-02-13", "%A %F").is_err());
+        assert!(strptime_strict("5 2009-02-13", "%u %F").is_ok());
+        assert!(strptime_strict("0 1970-01-04", "%w %F").is_ok());
+        assert!(strptime_strict("Fri Feb 13 15:31:30 2009", "%c").is_ok());
+        assert!(strptime_strict("Sat Feb 13 15:31:30 2009", "%c").is_err());
+
+        // Without a weekday, a year or a day of the month, there is
+        // nothing to check.
+        assert!(strptime_strict("13 Feb 2009", "%d %b %Y").is_ok());
+        assert!(strptime_strict("Fri 13 Feb", "%a %d %b").is_ok());
+        assert!(strptime_strict("Mon 13 Feb", "%a %d %b").is_ok());
+        assert!(strptime_strict("Fri 02/13/09", "%a %D").is_ok());
+        assert!(strptime_strict("Mon 02/13/09", "%a %D").is_err());
+        assert!(strptime_strict("Mon 15:31", "%a %H:%M").is_ok());
+        assert!(strptime_strict("%Mon, 13 Feb 2009", "%%Mon, %d %b %Y").is_ok());
+    }
+
+    fn test_parse_at_timestamp() {
+        assert_eq!(parse_at_timestamp("@1234567890.5"),
+                   Ok(Timespec::new(1234567890, 500_000_000)));
+        assert_eq!(parse_at_timestamp("@1234567890"), Ok(Timespec::new(1234567890, 0)));
+        assert_eq!(parse_at_timestamp(" @0.000000001 "), Ok(Timespec::new(0, 1)));
+        assert_eq!(parse_at_timestamp("@1.1234567899"), Ok(Timespec::new(1, 123456789)));
+        assert_eq!(parse_at_timestamp("@-1"), Ok(Timespec::new(-1, 0)));
+        assert_eq!(parse_at_timestamp("@-1.2"), Ok(Timespec::new(-2, 800_000_000)));
+
+        for s in ["1234567890", "@", "@.5", "@1.", "@1.5.2", "@12a", "@+1", "@--1",
+                  "@99999999999999999999"].iter() {
+            assert!(parse_at_timestamp(*s).is_err());
+        }
+    }
+
+    fn test_strptime_rfc2822() {
+        os::setenv("TZ", "America/Los_Angeles");
+        tzset();
+
+        let time = Timespec::new(1234567890, 0);
+        for tm in [at(time), at_utc(time)].iter() {
+            let s = tm.rfc822z();
+            let parsed = strptime_rfc2822(s).unwrap();
+            assert_eq!(parsed.tm_gmtoff, tm.tm_gmtoff);
+            assert_eq!(parsed.tm_wday, tm.tm_wday);
+            assert_eq!(parsed.field_cmp(tm), Equal);
+            assert_eq!(parsed.rfc822z(), s);
+        }
+
+        let tm = strptime_rfc2822("Fri, 13 Feb 2009 15:31:30 PST").unwrap();
+        assert_eq!(tm.tm_gmtoff, -28800_i32);
+        assert_eq!(tm.tm_zone, ~"PST");
+        assert_eq!(strptime_rfc2822(at_utc(time).rfc822()).unwrap().tm_gmtoff, 0_i32);
+
+        let tm = strptime_rfc2822("3 Feb 2009 05:04:00 +0530").unwrap();
+        assert_eq!(tm.tm_mday, 3_i32);
+        assert_eq!(tm.tm_hour, 5_i32);
+        assert_eq!(tm.tm_gmtoff, 19800_i32);
+        assert!(tm.rfc822z().ends_with("03 Feb 2009 05:04:00 +0530"));
+        // Without a weekday it is worked out from the date.
+        assert_eq!(tm.tm_wday, 2_i32);
+        assert_eq!(strptime_rfc2822("13 Feb 2009 23:31:30 +0000").unwrap().tm_wday, 5_i32);
+        assert!(strptime_rfc2822("Tue, 3 Feb 2009 05:04:00 +0530").is_ok());
+        assert!(strptime_rfc2822("Mon, 3 Feb 2009 05:04:00 +0530").is_err());
+        assert!(strptime_rfc2822("Fri, 13 Feb 2009 23:31:30 +0175").is_err());
+        assert!(strptime_rfc2822("Fri, 13 Feb 2009 23:31:30 -0060").is_err());
+        assert_eq!(strptime_rfc2822("Fri, 13 Feb 2009 23:31:30 -0959").unwrap().tm_gmtoff,
+                   -35940_i32);
+
+        assert!(strptime_rfc2822("Fri, 13 Feb 2009 23:31:30").is_err());
+        assert!(strptime_rfc2822("Fri, 13 Feb 2009 23:31:30 XYZ").is_err());
+        assert!(strptime_rfc2822("Fri, 13 Feb 2009 23:31:30 +05").is_err());
+        assert!(strptime_rfc2822("Fxi, 13 Feb 2009 23:31:30 +0000").is_err());
     }
 
     fn test_ctime() {
@@ -1222,8 +2774,8 @@ mod tests {
         assert_eq!(local.strftime("%e"), ~"13");
         assert_eq!(local.strftime("%f"), ~"000054321");
         assert_eq!(local.strftime("%F"), ~"2009-02-13");
-        // assert!(local.strftime("%G") == "2009");
-        // assert!(local.strftime("%g") == "09");
+        assert_eq!(local.strftime("%G"), ~"2009");
+        assert_eq!(local.strftime("%g"), ~"09");
         assert_eq!(local.strftime("%H"), ~"15");
         assert_eq!(local.strftime("%I"), ~"03");
         assert_eq!(local.strftime("%j"), ~"044");
@@ -1231,6 +2783,8 @@ mod tests {
         assert_eq!(local.strftime("%l"), ~" 3");
         assert_eq!(local.strftime("%M"), ~"31");
         assert_eq!(local.strftime("%m"), ~"02");
+        assert_eq!(local.strftime("%N"), ~"000054321");
+        assert_eq!(local.strftime("%T.%N"), local.strftime("%T.%f"));
         assert_eq!(local.strftime("%n"), ~"\n");
         assert_eq!(local.strftime("%P"), ~"pm");
         assert_eq!(local.strftime("%p"), ~"PM");
@@ -1240,14 +2794,14 @@ mod tests {
         assert_eq!(local.strftime("%s"), ~"1234567890");
         assert_eq!(local.strftime("%T"), ~"15:31:30");
         assert_eq!(local.strftime("%t"), ~"\t");
-        // assert!(local.strftime("%U") == "06");
+        assert_eq!(local.strftime("%U"), ~"06");
         assert_eq!(local.strftime("%u"), ~"5");
-        // assert!(local.strftime("%V") == "07");
+        assert_eq!(local.strftime("%V"), ~"07");
         assert_eq!(local.strftime("%v"), ~"13-Feb-2009");
-        // assert!(local.strftime("%W") == "06");
+        assert_eq!(local.strftime("%W"), ~"06");
         assert_eq!(local.strftime("%w"), ~"5");
-        // handle "%X"
-        // handle "%x"
+        assert_eq!(local.strftime("%X"), ~"15:31:30");
+        assert_eq!(local.strftime("%x"), ~"02/13/09");
         assert_eq!(local.strftime("%Y"), ~"2009");
         assert_eq!(local.strftime("%y"), ~"09");
 
@@ -1276,6 +2830,71 @@ mod tests {
         assert_eq!(utc.rfc3339(), ~"2009-02-13T23:31:30Z");
     }
 
+    fn test_strftime_week_numbers() {
+        let cases = [
+            (1234483200, "2009 09 07 06 06 044"), // Fri 2009-02-13
+            (1230508800, "2009 09 01 52 52 364"), // Mon 2008-12-29
+            (1262476800, "2009 09 53 01 00 003"), // Sun 2010-01-03
+            (1104537600, "2004 04 53 00 00 001"), // Sat 2005-01-01
+            (1356912000, "2013 13 01 53 53 366"), // Mon 2012-12-31
+            (946684800,  "1999 99 52 00 00 001"), // Sat 2000-01-01
+        ];
+        for &(sec, expected) in cases.iter() {
+            let tm = at_utc_pure(Timespec::new(sec, 0));
+            let all = tm.strftime("%G %g %V %U %W %j");
+            assert_eq!(all.as_slice(), expected);
+
+            // Each specifier agrees with itself when formatted alone.
+            let singles = ~[tm.strftime("%G"), tm.strftime("%g"), tm.strftime("%V"),
+                           tm.strftime("%U"), tm.strftime("%W"), tm.strftime("%j")];
+            assert_eq!(singles.connect(" "), all);
+        }
+    }
+
+    fn test_strftime_padded_edge_cases() {
+        // A day that was never set prints as 0 rather than being clamped.
+        let tm = empty_tm();
+        assert_eq!(tm.strftime("%d"), ~"00");
+        assert_eq!(tm.strftime("%e"), ~" 0");
+
+        let mut tm = empty_tm();
+        tm.tm_mday = 7_i32;
+        assert_eq!(tm.strftime("%d|%e"), ~"07| 7");
+
+        // Midnight and noon are both 12 on a 12-hour clock.
+        let expected = [(0_i32, "12|12| 0|AM"), (1_i32, "01| 1| 1|AM"),
+                        (11_i32, "11|11|11|AM"), (12_i32, "12|12|12|PM"),
+                        (13_i32, "01| 1|13|PM"), (23_i32, "11|11|23|PM")];
+        for &(hour, s) in expected.iter() {
+            tm.tm_hour = hour;
+            assert_eq!(tm.strftime("%I|%l|%k|%p"), s.to_owned());
+        }
+    }
+
+    fn test_strftime_no_padding() {
+        os::setenv("TZ", "America/Los_Angeles");
+        tzset();
+
+        // 2009-02-03 05:04:00 UTC
+        let utc = at_utc(Timespec::new(1233637440, 0));
+
+        assert_eq!(utc.strftime("%d"), ~"03");
+        assert_eq!(utc.strftime("%-d"), ~"3");
+        assert_eq!(utc.strftime("%e"), ~" 3");
+        assert_eq!(utc.strftime("%-e"), ~"3");
+        assert_eq!(utc.strftime("%-H:%M"), ~"5:04");
+        assert_eq!(utc.strftime("%-I %-l"), ~"5 5");
+        assert_eq!(utc.strftime("%-m/%-d/%y"), ~"2/3/09");
+        assert_eq!(utc.strftime("%-y"), ~"9");
+        assert_eq!(utc.strftime("%-j"), ~"34");
+        assert_eq!(utc.strftime("%-S"), ~"0");
+        assert_eq!(utc.strftime("%-b"), ~"Feb");
+
+        let later = at_utc(Timespec::new(1234567890, 0));
+        assert_eq!(later.strftime("%-d"), ~"13");
+        assert_eq!(later.strftime("%-e"), ~"13");
+    }
+
     fn test_timespec_eq_ord() {
         let a = &Timespec::new(-2, 1);
         let b = &Timespec::new(-1, 2);
@@ -1307,6 +2926,427 @@ mod tests {
         assert!(d.gt(c));
     }
 
+    // Conversions through a `TimeZone` never read `TZ`, so unlike the tests
+    // in `run_tests` this one is safe to run in parallel with them.
+    #[test]
+    fn test_at_zone() {
+        let time = Timespec::new(1234567890, 54321);
+
+        let pst = at_zone(time, &FixedOffset::new(-28800, "PST"));
+        assert_eq!(pst.strftime("%F %T"), ~"2009-02-13 15:31:30");
+        assert_eq!(pst.tm_wday, 5_i32);
+        assert_eq!(pst.tm_gmtoff, -28800_i32);
+        assert_eq!(pst.tm_zone, ~"PST");
+        assert_eq!(pst.tm_nsec, 54321_i32);
+        assert_eq!(pst.rfc3339(), ~"2009-02-13T15:31:30-08:00");
+
+        let ist = at_zone(time, &FixedOffset::new(19800, "IST"));
+        assert_eq!(ist.rfc3339(), ~"2009-02-14T05:01:30+05:30");
+        assert_eq!(ist.tm_yday, 44_i32);
+
+        assert_eq!(at_zone(time, &FixedOffset::utc()), at_utc(time));
+    }
+
+    #[test]
+    fn test_strftime_week_number_edges() {
+        // January 1st and December 31st falling on every day of the week,
+        // as "%G %g %V %U %W %j". A Saturday January 1st and a Monday
+        // December 31st are covered by `test_strftime_week_numbers`.
+        let cases = [
+            (978307200,  "2001 01 01 00 01 001"), // Mon 2001-01-01
+            (1009756800, "2002 02 01 52 53 365"), // Mon 2001-12-31
+            (1009843200, "2002 02 01 00 00 001"), // Tue 2002-01-01
+            (1041292800, "2003 03 01 52 52 365"), // Tue 2002-12-31
+            (1041379200, "2003 03 01 00 00 001"), // Wed 2003-01-01
+            (1072828800, "2004 04 01 52 52 365"), // Wed 2003-12-31
+            (1072915200, "2004 04 01 00 00 001"), // Thu 2004-01-01
+            (1104451200, "2004 04 53 52 52 366"), // Fri 2004-12-31
+            (1135987200, "2005 05 52 52 52 365"), // Sat 2005-12-31
+            (1136073600, "2005 05 52 01 00 001"), // Sun 2006-01-01
+            (1167523200, "2006 06 52 53 52 365"), // Sun 2006-12-31
+            (1199145600, "2008 08 01 00 00 001"), // Tue 2008-01-01
+            (1230681600, "2009 09 01 52 52 366"), // Wed 2008-12-31
+            (1262217600, "2009 09 53 52 52 365"), // Thu 2009-12-31
+            (1262304000, "2009 09 53 00 00 001"), // Fri 2010-01-01
+            (1293753600, "2010 10 52 52 52 365"), // Fri 2010-12-31
+            (1325289600, "2011 11 52 52 52 365"), // Sat 2011-12-31
+            (1325376000, "2011 11 52 01 00 001"), // Sun 2012-01-01
+        ];
+        for &(sec, expected) in cases.iter() {
+            let tm = at_utc_pure(Timespec::new(sec, 0));
+            assert_eq!(tm.strftime("%G %g %V %U %W %j"), expected.to_owned());
+        }
+
+        // Every day of the 53-week ISO years 2015 (a common year starting
+        // on a Thursday) and 2020 (a leap year starting on a Wednesday).
+        for &(start, year) in [(1419811200, "2015"), (1577664000, "2020")].iter() {
+            for day in range(0i64, 53 * 7) {
+                let tm = at_utc_pure(Timespec::new(start + day * 86400, 0));
+                assert_eq!(tm.strftime("%G"), year.to_owned());
+                assert_eq!(tm.strftime("%V"), format!("{:02d}", day / 7 + 1));
+                assert_eq!(tm.strftime("%u"), (day % 7 + 1).to_str());
+            }
+            // The next day starts week 1 of the following year.
+            let tm = at_utc_pure(Timespec::new(start + 53 * 7 * 86400, 0));
+            assert_eq!(tm.strftime("%V"), ~"01");
+            assert!(tm.strftime("%G") != year.to_owned());
+        }
+    }
+
+    #[test]
+    fn test_strptime_whitespace_directives() {
+        let tm = strptime("2009-02-13   \t23:31", "%Y-%m-%d%n%H:%M").unwrap();
+        assert_eq!((tm.tm_mday, tm.tm_hour, tm.tm_min), (13, 23, 31));
+        let tm = strptime("2009-02-13\n \n23:31", "%Y-%m-%d%t%H:%M").unwrap();
+        assert_eq!((tm.tm_mday, tm.tm_hour, tm.tm_min), (13, 23, 31));
+
+        // No whitespace at all is fine too, including at the end.
+        assert!(strptime("2009-02-1323:31", "%Y-%m-%d%n%H:%M").is_ok());
+        assert!(strptime("", "%n%t").is_ok());
+        let tm = strptime("23:31", "%H:%M%n").unwrap();
+        assert_eq!((tm.tm_hour, tm.tm_min), (23, 31));
+        assert!(strptime("23:31 \n", "%H:%M%t").is_ok());
+        assert_eq!(strptime("23:31", "%H:%M%n:%S"),
+                   Err(~"Input ended at position 5, expected ':'"));
+        // Anything else is not whitespace.
+        assert!(strptime("2009-02-13-23:31", "%Y-%m-%d%t%H:%M").is_err());
+    }
+
+    #[test]
+    fn test_meridiem_boundaries() {
+        let at_hour = |hour: i64, min: i64| at_utc_pure(Timespec::new(hour * 3600 + min * 60, 0));
+        let expected = [(0, 0, "12:00 AM am"), (0, 59, "12:59 AM am"),
+                        (1, 0, "01:00 AM am"), (11, 59, "11:59 AM am"),
+                        (12, 0, "12:00 PM pm"), (12, 59, "12:59 PM pm"),
+                        (13, 0, "01:00 PM pm"), (23, 0, "11:00 PM pm"),
+                        (23, 59, "11:59 PM pm")];
+        for &(hour, min, s) in expected.iter() {
+            let tm = at_hour(hour, min);
+            assert_eq!(tm.strftime("%I:%M %p %P"), s.to_owned());
+
+            // Parsing the 12-hour clock back gives the same hour.
+            let parsed = strptime(s.slice_to(8), "%I:%M %p").unwrap();
+            assert_eq!(parsed.tm_hour as i64, hour);
+            assert_eq!(parsed.tm_min as i64, min);
+        }
+    }
+
+    #[test]
+    #[should_fail]
+    fn test_round_to_out_of_range() {
+        Timespec::new(i64::max_value / 1_000_000_000 + 1, 0).round_to(1);
+    }
+
+    #[test]
+    #[should_fail]
+    fn test_round_to_rounded_out_of_range() {
+        // Representable as nanoseconds, but the step after it is not.
+        Timespec::new(9_223_372_036, 0).round_to(10_000_000_000);
+    }
+
+    #[test]
+    fn test_nth_weekday_of_month() {
+        let ymd = |tm: Option<Tm>| tm.map(|tm| tm.strftime("%Y-%m-%d %A"));
+
+        // third Thursday of November 2024
+        assert_eq!(ymd(nth_weekday_of_month(2024, 10, 4, 3)),
+                   Some(~"2024-11-21 Thursday"));
+        // last Monday of May 2024
+        assert_eq!(ymd(nth_weekday_of_month(2024, 4, 1, -1)),
+                   Some(~"2024-05-27 Monday"));
+
+        // the month starts and ends on the weekday asked for
+        assert_eq!(ymd(nth_weekday_of_month(2024, 10, 5, 1)),
+                   Some(~"2024-11-01 Friday"));
+        assert_eq!(ymd(nth_weekday_of_month(2024, 4, 5, -1)),
+                   Some(~"2024-05-31 Friday"));
+        assert_eq!(ymd(nth_weekday_of_month(2024, 4, 5, -5)),
+                   Some(~"2024-05-03 Friday"));
+        assert_eq!(ymd(nth_weekday_of_month(2024, 1, 4, 5)),
+                   Some(~"2024-02-29 Thursday"));
+
+        // no such day
+        assert_eq!(nth_weekday_of_month(2024, 1, 5, 5), None);
+        assert_eq!(nth_weekday_of_month(2023, 1, 4, -5), None);
+        assert_eq!(nth_weekday_of_month(2024, 10, 4, 0), None);
+
+        let tm = nth_weekday_of_month(1900, 0, 1, 1).unwrap();
+        assert_eq!(tm.to_timespec().sec % 86400, 0);
+        assert_eq!((tm.tm_mday, tm.tm_wday, tm.tm_yday), (1, 1, 0));
+    }
+
+    #[test]
+    #[should_fail]
+    fn test_nth_weekday_of_month_bad_month() {
+        nth_weekday_of_month(2024, 12, 0, 1);
+    }
+
+    #[test]
+    fn test_utc_to_tai_offset() {
+        // The leap second 2016-12-31T23:59:60Z took the offset to 37.
+        let new_year_2017 = Timespec::new(1483228800, 0);
+        assert_eq!(utc_to_tai_offset(&new_year_2017), 37);
+        assert_eq!(utc_to_tai_offset(&Timespec::new(1483228799, 999_999_999)), 36);
+        assert_eq!(utc_to_tai_offset(&Timespec::new(1600000000, 0)), 37);
+        assert_eq!(utc_to_tai_offset(&Timespec::new(1234567890, 0)), 34);
+        assert_eq!(utc_to_tai_offset(&Timespec::new(63072000, 0)), 10);
+        assert_eq!(utc_to_tai_offset(&Timespec::new(0, 0)), 10);
+        assert_eq!(utc_to_tai_offset(&Timespec::new(78796800, 0)), 11);
+
+        // One UTC second across the leap second took two real seconds.
+        let before = Timespec::new(1483228799, 0);
+        let tai_before = convert_time_scale(&before, UTC, TAI);
+        let tai_after = convert_time_scale(&new_year_2017, UTC, TAI);
+        assert_eq!(tai_after.sec - tai_before.sec, 2);
+
+        for &sec in [0i64, 1483228799, 1483228800, 1341100799, 1600000000].iter() {
+            let utc = Timespec::new(sec, 123);
+            let tai = convert_time_scale(&utc, UTC, TAI);
+            assert_eq!(convert_time_scale(&tai, TAI, UTC), utc);
+            assert_eq!(convert_time_scale(&tai, TAI, TAI), tai);
+        }
+        // The leap second itself has no UTC name of its own.
+        let leap = Timespec::new(tai_before.sec + 1, 0);
+        assert_eq!(convert_time_scale(&leap, TAI, UTC), new_year_2017);
+    }
+
+    #[test]
+    fn test_to_c_tm() {
+        let mut tm = at_utc_pure(Timespec::new(1234567890, 54321));
+        tm.tm_gmtoff = -28800;
+        tm.tm_isdst = 1;
+        let c = tm.to_c_tm();
+        assert_eq!((c.tm_sec, c.tm_min, c.tm_hour), (30, 31, 23));
+        // February 13th, 2009, a Friday: C counts months from 0 and years
+        // from 1900 just as Tm does.
+        assert_eq!((c.tm_mday, c.tm_mon, c.tm_year), (13, 1, 109));
+        assert_eq!((c.tm_wday, c.tm_yday, c.tm_isdst), (5, 43, 1));
+        check_c_tm_zone(&c);
+
+        #[cfg(not(target_os = "win32"))]
+        fn check_c_tm_zone(c: &CTm) {
+            assert_eq!(c.tm_gmtoff, -28800);
+            assert!(c.tm_zone.is_null());
+        }
+        #[cfg(target_os = "win32")]
+        fn check_c_tm_zone(_c: &CTm) {}
+    }
+
+    #[test]
+    fn test_tm_ref() {
+        let ts = Timespec::new(1234567890, 54321);
+        let tm = at_utc_ref(ts);
+        assert_eq!(tm.tm_zone, "UTC");
+        assert_eq!(tm.to_tm(), at_utc_pure(ts));
+        assert_eq!(tm.to_tm().as_ref(), tm);
+        let format = "%a %b %e %H:%M:%S.%f %Z %Y %s %j";
+        assert_eq!(tm.strftime(format), at_utc_pure(ts).strftime(format));
+        assert_eq!(tm.strftime(format),
+                   ~"Fri Feb 13 23:31:30.000054321 UTC 2009 1234567890 044");
+
+        let mut local = at_utc_pure(ts);
+        local.tm_zone = ~"PST";
+        assert_eq!(local.as_ref().strftime("%Z"), ~"PST");
+    }
+
+    #[test]
+    fn test_solar_offset_seconds() {
+        assert_eq!(solar_offset_seconds(0.0), 0_i32);
+        assert_eq!(solar_offset_seconds(-75.0), -5_i32 * 3600);
+        assert_eq!(solar_offset_seconds(135.0), 9_i32 * 3600);
+        assert_eq!(solar_offset_seconds(180.0), 12_i32 * 3600);
+        // One degree is four minutes.
+        assert_eq!(solar_offset_seconds(1.0), 240_i32);
+        assert_eq!(solar_offset_seconds(-0.5), -120_i32);
+    }
+
+    #[test]
+    fn test_gen_event_times() {
+        let mut rng = rand::rng();
+        let start = Timespec::new(1234567890, 999_999_000);
+        let end = Timespec::new(1234571490, 500);
+        let times = rng.gen_event_times(start, end, 1000);
+        assert_eq!(times.len(), 1000);
+        for t in times.iter() {
+            assert!(*t >= start && *t < end);
+        }
+        for pair in times.window_iter(2) {
+            assert!(pair[0] <= pair[1]);
+        }
+        // The times are spread over the whole hour.
+        assert!(times[0].sec < start.sec + 60);
+        assert!(times[999].sec > end.sec - 60);
+
+        // A one nanosecond window has only one possible time.
+        let last = Timespec::new(start.sec, 999_999_999);
+        let next = Timespec::new(start.sec + 1, 0);
+        assert_eq!(rng.gen_event_times(last, next, 3), ~[last, last, last]);
+        assert!(rng.gen_event_times(end, start, 0).is_empty());
+    }
+
+    #[test]
+    #[should_fail]
+    fn test_gen_event_times_empty_window() {
+        let mut rng = rand::rng();
+        let t = Timespec::new(0, 0);
+        rng.gen_event_times(t, t, 1);
+    }
+
+    #[test]
+    fn test_gen_date() {
+        static DAYS: [i32, ..12] = [31, 28, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31];
+        let mut rng = rand::rng();
+        let mut leap_days = 0;
+        for _ in range(0, 10000) {
+            let tm = rng.gen_date(1999, 2001);
+            let year = tm.tm_year + 1900;
+            assert!(year >= 1999 && year <= 2001);
+            assert!(tm.tm_mon >= 0 && tm.tm_mon < 12);
+            let max_mday = if tm.tm_mon == 1 && year == 2000 { 29 } else { DAYS[tm.tm_mon as uint] };
+            assert!(tm.tm_mday >= 1 && tm.tm_mday <= max_mday);
+            if tm.tm_mon == 1 && tm.tm_mday == 29 {
+                leap_days += 1;
+            }
+            assert_eq!((tm.tm_hour, tm.tm_min, tm.tm_sec, tm.tm_nsec), (0, 0, 0, 0));
+
+            // The weekday and day of the year agree with the date.
+            let s = tm.strftime("%a %F");
+            assert!(strptime_strict(s, "%a %F").is_ok());
+            assert_eq!(tm.strftime("%j"),
+                       at_utc_pure(tm.to_timespec()).strftime("%j"));
+        }
+        // About 1 in 3 * 12 * 29 draws.
+        assert!(leap_days > 0);
+
+        let tm = rng.gen_date(2009, 2009);
+        assert_eq!(tm.tm_year, 109_i32);
+    }
+
+    // Formats random times with random combinations of specifiers and
+    // checks that parsing the result gives back the fields they print. Like
+    // `test_at_zone`, nothing here reads `TZ`.
+    #[test]
+    fn test_strptime_strftime_round_trip() {
+        // `%y` only round-trips between 1969 and 2068, and `%I` with `%p`
+        // is left out because it cannot represent midnight and noon.
+        let specifiers = ['Y', 'y', 'm', 'b', 'B', 'd', 'e', 'F', 'H', 'k', 'M',
+                          'S', 'T', 'f', 'j', 'a', 'A', 'w', 'u'];
+
+        fn same(spec: char, a: &Tm, b: &Tm) -> bool {
+            match spec {
+                'Y' | 'y' => a.tm_year == b.tm_year,
+                'm' | 'b' | 'B' => a.tm_mon == b.tm_mon,
+                'd' | 'e' => a.tm_mday == b.tm_mday,
+                'F' => same('Y', a, b) && same('m', a, b) && same('d', a, b),
+                'H' | 'k' => a.tm_hour == b.tm_hour,
+                'M' => a.tm_min == b.tm_min,
+                'S' => a.tm_sec == b.tm_sec,
+                'T' => same('H', a, b) && same('M', a, b) && same('S', a, b),
+                'f' => a.tm_nsec == b.tm_nsec,
+                'j' => a.tm_yday == b.tm_yday,
+                'a' | 'A' | 'w' | 'u' => a.tm_wday == b.tm_wday,
+                _ => fail2!("no field for %{}", spec)
+            }
+        }
+
+        let mut rng = rand::rng();
+        for _ in range(0, 1000) {
+            let mut tm = rng.gen_date(1969, 2068);
+            tm.tm_hour = rng.gen_integer_range(0_i32, 24);
+            tm.tm_min = rng.gen_integer_range(0_i32, 60);
+            tm.tm_sec = rng.gen_integer_range(0_i32, 60);
+            tm.tm_nsec = rng.gen_integer_range(0_i32, 1_000_000_000);
+
+            let mut chosen = specifiers.to_owned();
+            rng.shuffle_mut(chosen);
+            let n = rng.gen_integer_range(1u, chosen.len() + 1);
+            let chosen = chosen.slice_to(n);
+            let format = chosen.map(|c| format!("%{}", *c)).connect("|");
+
+            let s = tm.strftime(format);
+            let parsed = match strptime(s, format) {
+                Ok(parsed) => parsed,
+                Err(e) => fail2!("strptime({}, {}) failed: {}", s, format, e)
+            };
+            for &spec in chosen.iter() {
+                assert!(same(spec, &tm, &parsed),
+                        "%{} did not round-trip: {} with {}", spec, s, format);
+            }
+        }
+    }
+
+    #[test]
+    fn test_weekly_iter() {
+        // Friday 2009-02-13 15:31:30 PST.
+        let start = at_zone(Timespec::new(1234567890, 54321), &FixedOffset::new(-28800, "PST"));
+
+        let mondays: ~[Tm] = start.weekly_iter(1).take(4).collect();
+        assert_eq!(mondays.map(|tm| tm.strftime("%a %F %T %z")),
+                   ~[~"Mon 2009-02-16 15:31:30 -0800", ~"Mon 2009-02-23 15:31:30 -0800",
+                     ~"Mon 2009-03-02 15:31:30 -0800", ~"Mon 2009-03-09 15:31:30 -0800"]);
+        for tm in mondays.iter() {
+            assert_eq!(tm.tm_wday, 1_i32);
+            assert_eq!(tm.tm_nsec, 54321_i32);
+            assert_eq!(tm.tm_zone, ~"PST");
+        }
+
+        // The start date itself is included when it is on the weekday.
+        let fridays: ~[Tm] = start.weekly_iter(5).take(2).collect();
+        assert_eq!(fridays[0].strftime("%F"), ~"2009-02-13");
+        assert_eq!(fridays[1].strftime("%F"), ~"2009-02-20");
+
+        // Across a year end, and from a date whose `tm_wday` was never set.
+        let mut tm = empty_tm();
+        tm.tm_year = 109_i32;
+        tm.tm_mon = 11_i32;
+        tm.tm_mday = 31_i32;
+        let sundays: ~[Tm] = tm.weekly_iter(0).take(2).collect();
+        assert_eq!(sundays[0].strftime("%a %F"), ~"Sun 2010-01-03");
+        assert_eq!(sundays[1].strftime("%a %F"), ~"Sun 2010-01-10");
+    }
+
+    #[test]
+    fn test_nano_time() {
+        use ebml::{reader, writer};
+        use serialize::{Decodable, Encodable};
+        use std::io;
+
+        let times = [Timespec::new(0, 0), Timespec::new(1234567890, 54321),
+                     Timespec::new(-1, 800_000_000), Timespec::new(-86400, 0)];
+        for ts in times.iter() {
+            let nt = NanoTime::from_timespec(*ts).unwrap();
+            assert_eq!(nt.to_timespec(), *ts);
+
+            let bytes = do io::with_bytes_writer |wr| {
+                let mut encoder = writer::Encoder(wr);
+                nt.encode(&mut encoder);
+            };
+            let mut decoder = reader::Decoder(reader::Doc(@bytes));
+            let decoded: NanoTime = Decodable::decode(&mut decoder);
+            assert_eq!(decoded, nt);
+        }
+        assert_eq!(NanoTime::from_timespec(Timespec::new(-1, 800_000_000)),
+                   Some(NanoTime(-200_000_000)));
+        assert!(NanoTime(-1) < NanoTime(0));
+    }
+
+    #[test]
+    fn test_nano_time_overflow() {
+        // i64::max_value nanoseconds is 2262-04-11T23:47:16.854775807Z.
+        let max = Timespec::new(9223372036, 854775807);
+        assert_eq!(NanoTime::from_timespec(max), Some(NanoTime(i64::max_value)));
+        assert_eq!(NanoTime(i64::max_value).to_timespec(), max);
+        assert!(NanoTime::from_timespec(Timespec::new(9223372036, 854775808)).is_none());
+        assert!(NanoTime::from_timespec(Timespec::new(9223372037, 0)).is_none());
+
+        // i64::min_value nanoseconds is 1677-09-21T00:12:43.145224192Z.
+        let min = Timespec::new(-9223372037, 145224192);
+        assert_eq!(NanoTime::from_timespec(min), Some(NanoTime(i64::min_value)));
+        assert_eq!(NanoTime(i64::min_value).to_timespec(), min);
+        assert!(NanoTime::from_timespec(Timespec::new(-9223372037, 145224191)).is_none());
+        assert!(NanoTime::from_timespec(Timespec::new(i64::min_value, 0)).is_none());
+    }
+
     #[test]
     fn run_tests() {
         // The tests race on tzset. So instead of having many independent
@@ -1314,12 +3354,49 @@ mod tests {
         test_get_time();
         test_precise_time();
         test_at_utc();
+        test_at_utc_pure();
+        test_to_iso8601_utc();
+        test_from_iso8601();
         test_at();
         test_to_timespec();
+        test_with_dst();
+        test_field_cmp();
+        test_instant();
+        test_new_normalized();
+        test_approx_eq();
+        test_round_to();
+        test_whole_years_until();
         test_conversions();
         test_strptime();
+        test_strptime_strict();
+        test_strptime_lenient();
+        test_parse_at_timestamp();
+        test_strptime_rfc2822();
         test_ctime();
         test_strftime();
+        test_strftime_no_padding();
+        test_strftime_week_numbers();
+        test_strftime_padded_edge_cases();
         test_timespec_eq_ord();
     }
+
+    // Formatting through a `TmRef` skips allocating and freeing the zone
+    // name of each intermediate `Tm`.
+    #[bench]
+    fn bench_strftime_utc_owned(b: &mut BenchHarness) {
+        let mut sec = 1234567890;
+        do b.iter {
+            at_utc_pure(Timespec::new(sec, 0)).strftime("%F %T %Z");
+            sec += 1;
+        }
+    }
+
+    #[bench]
+    fn bench_strftime_utc_ref(b: &mut BenchHarness) {
+        let mut sec = 1234567890;
+        do b.iter {
+            at_utc_ref(Timespec::new(sec, 0)).strftime("%F %T %Z");
+            sec += 1;
+        }
+    }
 }
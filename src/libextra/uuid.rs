@@ -65,6 +65,7 @@ use std::rand;
 use std::rand::Rng;
 use std::cmp::Eq;
 use std::cast::{transmute,transmute_copy};
+use time::Timespec;
 
 /// A 128-bit (16 byte) buffer containing the ID
 pub type UuidBytes = [u8, ..16];
@@ -82,6 +83,8 @@ pub enum UuidVersion {
     Version4Random = 4,
     /// Version 5: SHA-1 hash
     Version5Sha1   = 5,
+    /// Version 7: Unix time in milliseconds, then random
+    Version7Time   = 7,
 }
 
 /// The reserved variants of UUIDs
@@ -288,6 +291,7 @@ impl Uuid {
             3 => Some(Version3Md5),
             4 => Some(Version4Random),
             5 => Some(Version5Sha1),
+            7 => Some(Version7Time),
             _ => None
         }
     }
@@ -498,6 +502,37 @@ impl rand::Rand for Uuid {
     }
 }
 
+/// Extension methods for generating time-ordered UUIDs with any `Rng`.
+pub trait UuidRng {
+    /// Returns a version 7 UUID for the time `now`, in hyphenated form.
+    ///
+    /// The first 48 bits hold the Unix time in milliseconds and all bits
+    /// other than the version and variant are random, so UUIDs made at
+    /// increasing times (at least a millisecond apart) sort in the same order
+    /// as strings, which makes them good database keys. Fails if `now` is
+    /// before the epoch.
+    fn gen_uuid_v7(&mut self, now: Timespec) -> ~str;
+}
+
+impl<R: Rng> UuidRng for R {
+    fn gen_uuid_v7(&mut self, now: Timespec) -> ~str {
+        assert!(now.sec >= 0, "UuidRng.gen_uuid_v7 called with a pre-epoch time");
+        let millis = now.sec as u64 * 1000 + now.nsec as u64 / 1000000;
+
+        let mut uuid = Uuid{ bytes: [0, .. 16] };
+        for i in range(0u, 16) {
+            uuid.bytes[i] = if i < 6 {
+                (millis >> (8 * (5 - i))) as u8
+            } else {
+                self.gen()
+            };
+        }
+        uuid.set_variant(VariantRFC4122);
+        uuid.set_version(Version7Time);
+        uuid.to_hyphenated_str()
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -760,6 +795,32 @@ mod test {
         assert!(u3 != u2);
     }
 
+    #[test]
+    fn test_gen_uuid_v7() {
+        use time::Timespec;
+
+        let mut rng = rand::rng();
+        let u1 = rng.gen_uuid_v7(Timespec::new(1234567890, 0));
+        let u2 = rng.gen_uuid_v7(Timespec::new(1234567890, 1000000));
+        let u3 = rng.gen_uuid_v7(Timespec::new(1234567891, 0));
+        assert!(u1 < u2);
+        assert!(u2 < u3);
+
+        // 1234567890000 milliseconds is 0x011f71fb0450.
+        assert!(u1.starts_with("011f71fb-0450-7"));
+        let uuid: Uuid = FromStr::from_str(u1).unwrap();
+        assert!(uuid.get_version() == Some(Version7Time));
+        assert!(uuid.get_variant() == Some(VariantRFC4122));
+
+        for _ in range(0, 100) {
+            let a = rng.gen_uuid_v7(Timespec::new(1234567890, 0));
+            let b = rng.gen_uuid_v7(Timespec::new(1234567890, 999999));
+            let c = rng.gen_uuid_v7(Timespec::new(1234567890, 1000000));
+            assert!(a.slice_to(13) == b.slice_to(13));
+            assert!(b < c);
+        }
+    }
+
     #[test]
     fn test_rand_rand() {
         let mut rng = rand::rng();
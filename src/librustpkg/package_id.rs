@@ -9,7 +9,8 @@
 // except according to those terms.
 
 use version::{try_getting_version, try_getting_local_version,
-              Version, NoVersion, split_version};
+              try_getting_git_head_version, Version, NoVersion, Tagged,
+              split_version};
 use std::rt::io::Writer;
 use std::hash::Streaming;
 use std::hash;
@@ -59,6 +60,11 @@ impl PkgId {
             }
         };
 
+        // "foo/bar/" names the same package as "foo/bar", so drop trailing
+        // slashes rather than letting them change the path's components.
+        let trimmed = s.trim_right_chars(&'/');
+        let s = if trimmed.is_empty() { s } else { trimmed };
+
         let path = Path(s);
         if path.is_absolute {
             return cond.raise((path, ~"absolute pkgid"));
@@ -68,13 +74,27 @@ impl PkgId {
         }
         let short_name = path.filestem().expect(format!("Strange path! {}", s));
 
+        // A version tag on the checked-out commit wins over the repo's
+        // other tags; failing both, the commit hash itself is the version.
+        let head = match given_version {
+            Some(_) => None,
+            None => try_getting_git_head_version(&path)
+        };
+        let head_is_tagged = match head {
+            Some(Tagged(_)) | None => false,
+            Some(_) => true
+        };
         let version = match given_version {
             Some(v) => v,
+            None if head_is_tagged => head.unwrap(),
             None => match try_getting_local_version(&path) {
                 Some(v) => v,
-                None => match try_getting_version(&path) {
+                None => match head {
                     Some(v) => v,
-                    None => NoVersion
+                    None => match try_getting_version(&path) {
+                        Some(v) => v,
+                        None => NoVersion
+                    }
                 }
             }
         };
@@ -101,6 +121,47 @@ impl PkgId {
         self.short_name != self.path.to_str()
     }
 
+    /// Returns the ID of the package enclosing this one, found by dropping
+    /// the last path component, or None if the path has only one component.
+    /// The parent's version is unknown, so it is always `NoVersion`.
+    pub fn parent(&self) -> Option<PkgId> {
+        if self.path.components.len() <= 1 {
+            return None;
+        }
+        let path = self.path.pop();
+        let short_name = path.filestem().expect(format!("Strange path! {}",
+                                                        path.to_str()));
+        Some(PkgId {
+            short_name: short_name.to_owned(),
+            path: path,
+            version: NoVersion
+        })
+    }
+
+    /// Returns the host the package comes from, such as "github.com": the
+    /// first path component if it looks like a domain name (contains a dot)
+    /// and is followed by more components. Returns None for local ids.
+    pub fn host(&self) -> Option<~str> {
+        let components = self.path.components();
+        if components.len() > 1 && components[0].contains_char('.') {
+            Some(components[0].clone())
+        } else {
+            None
+        }
+    }
+
+    /// Returns the short name with every character that is illegal in a
+    /// file name on some platform (`<>:"/\\|?*` and control characters)
+    /// replaced by `_`, for use as a path component. `short_name` itself
+    /// is left as-is.
+    pub fn fs_safe_name(&self) -> ~str {
+        self.short_name.iter().map(|c| match c {
+            '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' => '_',
+            c if c.is_control() => '_',
+            c => c
+        }).collect()
+    }
+
     pub fn prefixes_iter(&self) -> Prefixes {
         Prefixes {
             components: self.path.components().to_owned(),
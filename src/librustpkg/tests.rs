@@ -22,7 +22,7 @@ use extra::getopts::groups::getopts;
 use std::run::ProcessOutput;
 use installed_packages::list_installed_packages;
 use package_id::{PkgId};
-use version::{ExactRevision, NoVersion, Version, Tagged};
+use version::{ExactRevision, NoVersion, Version, Tagged, git_head_version};
 use path_util::{target_executable_in_workspace, target_test_in_workspace,
                target_bench_in_workspace, make_dir_rwx, U_RWX,
                library_in_workspace, installed_library_in_workspace,
@@ -1860,6 +1860,137 @@ fn test_no_rebuilding() {
     }
 }
 
+#[test]
+fn test_pkgid_parent() {
+    let id = PkgId {
+        path: Path("github.com/foo/bar"),
+        short_name: ~"bar",
+        version: ExactRevision(~"0.2")
+    };
+    let parent = id.parent().expect("test_pkgid_parent: no parent");
+    assert_eq!(parent.path, Path("github.com/foo"));
+    assert_eq!(parent.short_name, ~"foo");
+    match parent.version {
+        NoVersion => (),
+        _ => fail2!("test_pkgid_parent: parent should have no version")
+    }
+
+    let grandparent = parent.parent().expect("test_pkgid_parent: no grandparent");
+    assert_eq!(grandparent.path, Path("github.com"));
+    assert!(grandparent.parent().is_none());
+    assert!(fake_pkg().parent().is_none());
+}
+
+#[test]
+fn test_pkgid_host() {
+    let id = PkgId {
+        path: Path("github.com/foo/bar"),
+        short_name: ~"bar",
+        version: NoVersion
+    };
+    assert_eq!(id.host(), Some(~"github.com"));
+    assert_eq!(git_repo_pkg().host(), Some(~"mockgithub.com"));
+
+    let id = PkgId {
+        path: Path("mylib"),
+        short_name: ~"mylib",
+        version: NoVersion
+    };
+    assert_eq!(id.host(), None);
+
+    let id = PkgId {
+        path: Path("foo/bar"),
+        short_name: ~"bar",
+        version: NoVersion
+    };
+    assert_eq!(id.host(), None);
+}
+
+#[test]
+fn test_pkgid_fs_safe_name() {
+    let id = PkgId {
+        path: Path("foo/bar"),
+        short_name: ~"b:a\\r|baz?*",
+        version: NoVersion
+    };
+    assert_eq!(id.fs_safe_name(), ~"b_a_r_baz__");
+    assert_eq!(id.short_name, ~"b:a\\r|baz?*");
+    assert_eq!(fake_pkg().fs_safe_name(), fake_pkg().short_name);
+}
+
+#[test]
+fn test_pkgid_trailing_slash() {
+    let with_slash = PkgId::new("github.com/foo/bar/#0.2");
+    let without = PkgId::new("github.com/foo/bar#0.2");
+    assert_eq!(with_slash, without);
+    assert_eq!(with_slash.short_name, ~"bar");
+    assert_eq!(with_slash.short_name, without.short_name);
+    assert_eq!(with_slash.path.components.len(), 3);
+
+    let with_slashes = PkgId::new("github.com/foo/bar//#0.2");
+    assert_eq!(with_slashes, without);
+    assert_eq!(with_slashes.short_name, without.short_name);
+}
+
+#[test]
+fn test_git_head_version() {
+    static COMMIT: &'static str = "3f786850e387550fdab836ed7e6dc881de23001b";
+    static OTHER: &'static str = "89e6c98d92887913cadf06b2adb97f26cde4849b";
+    let repo = mkdtemp(&os::tmpdir(), "git_head_version").expect("couldn't create temp dir");
+    let git_dir = repo.push(".git");
+    assert_eq!(git_head_version(&repo), None);
+
+    // HEAD on a branch, with no tags: the commit hash is the version.
+    assert!(os::mkdir_recursive(&git_dir.push_many(["refs", "heads"]), U_RWX));
+    assert!(os::mkdir_recursive(&git_dir.push_many(["refs", "tags"]), U_RWX));
+    writeFile(&git_dir.push("HEAD"), "ref: refs/heads/master");
+    writeFile(&git_dir.push_many(["refs", "heads", "master"]), COMMIT);
+    assert_eq!(git_head_version(&repo), Some(Tagged(COMMIT.to_owned())));
+
+    // Tags on other commits, or that aren't versions, are ignored.
+    writeFile(&git_dir.push_many(["refs", "tags", "0.1"]), OTHER);
+    writeFile(&git_dir.push_many(["refs", "tags", "latest"]), COMMIT);
+    assert_eq!(git_head_version(&repo), Some(Tagged(COMMIT.to_owned())));
+
+    writeFile(&git_dir.push_many(["refs", "tags", "0.3"]), COMMIT);
+    assert_eq!(git_head_version(&repo), Some(ExactRevision(~"0.3")));
+    // With several version tags on HEAD, the highest one wins.
+    writeFile(&git_dir.push_many(["refs", "tags", "0.10"]), COMMIT);
+    writeFile(&git_dir.push_many(["refs", "tags", "0.9"]), COMMIT);
+    assert_eq!(git_head_version(&repo), Some(ExactRevision(~"0.10")));
+    os::remove_file(&git_dir.push_many(["refs", "tags", "0.10"]));
+    os::remove_file(&git_dir.push_many(["refs", "tags", "0.9"]));
+
+    // A detached HEAD, and a packed branch and annotated tag.
+    os::remove_file(&git_dir.push_many(["refs", "tags", "0.3"]));
+    os::remove_file(&git_dir.push_many(["refs", "heads", "master"]));
+    let packed = ~"# pack-refs with: peeled\n" + COMMIT + " refs/heads/master\n" +
+        OTHER + " refs/tags/1.2.3-rc.1\n^" + COMMIT;
+    writeFile(&git_dir.push("packed-refs"), packed);
+    assert_eq!(git_head_version(&repo), Version::parse("1.2.3-rc.1"));
+    // The annotated tag object is OTHER, but it peels to COMMIT.
+    writeFile(&git_dir.push("HEAD"), OTHER);
+    assert_eq!(git_head_version(&repo), Some(ExactRevision(~"0.1")));
+}
+
+#[test]
+fn test_pkg_id_git_head_version() {
+    static COMMIT: &'static str = "3f786850e387550fdab836ed7e6dc881de23001b";
+    let dir = mkdtemp(&os::tmpdir(), "pkg_id_git_head_version").expect("couldn't create temp dir");
+    let git_dir = dir.push_many(["fake_pkg", ".git"]);
+    assert!(os::mkdir_recursive(&git_dir.push_many(["refs", "tags"]), U_RWX));
+    writeFile(&git_dir.push("HEAD"), COMMIT);
+    writeFile(&git_dir.push_many(["refs", "tags", "0.9"]), COMMIT);
+    writeFile(&git_dir.push_many(["refs", "tags", "0.10"]), COMMIT);
+
+    os::setenv("RUST_PATH", dir.to_str());
+    // The highest tag at HEAD is found without running git.
+    assert_eq!(PkgId::new("fake_pkg").version, ExactRevision(~"0.10"));
+    // An explicit version still wins.
+    assert_eq!(PkgId::new("fake_pkg#0.3").version, ExactRevision(~"0.3"));
+    os::unsetenv("RUST_PATH");
+}
+
 /// Returns true if p exists and is executable
 fn is_executable(p: &Path) -> bool {
     use std::libc::consts::os::posix88::{S_IXUSR};
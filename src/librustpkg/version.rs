@@ -14,7 +14,8 @@
 extern mod std;
 
 use extra::semver;
-use std::{char, os, result, run, str};
+use extra::serialize::{Encodable, Decodable, Encoder, Decoder};
+use std::{char, io, os, result, run, str};
 use extra::tempfile::mkdtemp;
 use path_util::rust_path;
 
@@ -36,6 +37,7 @@ impl Eq for Version {
         match (self, other) {
             (&ExactRevision(ref s1), &ExactRevision(ref s2)) => *s1 == *s2,
             (&SemanticVersion(ref v1), &SemanticVersion(ref v2)) => *v1 == *v2,
+            (&Tagged(ref s1), &Tagged(ref s2)) => *s1 == *s2,
             (&NoVersion, _) => true,
             _ => false
         }
@@ -76,6 +78,35 @@ impl Ord for Version {
 
 }
 
+impl Version {
+    /// Parses a version in the canonical form that `to_str` prints, so that
+    /// `Version::parse(v.to_str()) == Some(v)` for every version `v` that
+    /// `parse` returns.
+    ///
+    /// Dotted numbers such as "1.2" or "1.0.17" give an `ExactRevision` that
+    /// keeps exactly the components that were written; no ".0" is added.
+    /// Semantic versions with a pre-release or build part, such as
+    /// "1.2.3-rc.1", give a `SemanticVersion`, and any other string is
+    /// `Tagged`. Returns `None` if `s` is empty or all whitespace.
+    pub fn parse(s: &str) -> Option<Version> {
+        let s = s.trim();
+        if s.is_empty() {
+            return None;
+        }
+        match try_parsing_version(s) {
+            Some(v) => Some(v),
+            None => match semver::parse(s) {
+                Some(v) => Some(SemanticVersion(v)),
+                None => Some(Tagged(s.to_owned()))
+            }
+        }
+    }
+}
+
+// Exact revisions and tags print exactly as they were written. A semantic
+// version without a pre-release or build part prints as plain dotted
+// numbers, and so parses back as the equivalent `ExactRevision`. `NoVersion`
+// prints as the default version 0.1.
 impl ToStr for Version {
     fn to_str(&self) -> ~str {
         match *self {
@@ -86,6 +117,30 @@ impl ToStr for Version {
     }
 }
 
+impl FromStr for Version {
+    fn from_str(s: &str) -> Option<Version> {
+        Version::parse(s)
+    }
+}
+
+// Versions are serialized as their canonical string form, so that they
+// read naturally in lockfiles and other stored metadata.
+impl<S: Encoder> Encodable<S> for Version {
+    fn encode(&self, s: &mut S) {
+        self.to_str().encode(s)
+    }
+}
+
+impl<D: Decoder> Decodable<D> for Version {
+    fn decode(d: &mut D) -> Version {
+        let s: ~str = Decodable::decode(d);
+        match Version::parse(s) {
+            Some(v) => v,
+            None => fail2!("Version::decode: invalid version '{}'", s)
+        }
+    }
+}
+
 pub fn parse_vers(vers: ~str) -> result::Result<semver::Version, ~str> {
     match semver::parse(vers) {
         Some(vers) => result::Ok(vers),
@@ -127,6 +182,160 @@ pub fn try_getting_local_version(local_path: &Path) -> Option<Version> {
   None
 }
 
+/// If `local_path` is a git checkout in the RUST_PATH, return the version
+/// given by its checked-out commit, as `git_head_version` does; otherwise,
+/// `None`. Unlike `try_getting_local_version` this reads the repository
+/// files directly, so it works without a `git` executable and for
+/// repositories without any version tags.
+pub fn try_getting_git_head_version(local_path: &Path) -> Option<Version> {
+    for rp in rust_path().iter() {
+        match git_head_version(&rp.push_rel(local_path)) {
+            Some(v) => return Some(v),
+            None => ()
+        }
+    }
+    None
+}
+
+/// Reads `.git/HEAD` in the checkout at `repo_dir` and returns the highest
+/// version named by a tag pointing at the checked-out commit, or `Tagged`
+/// with the commit hash if no such tag denotes a version. Returns `None` if
+/// `repo_dir` is not a git checkout or HEAD can't be resolved.
+///
+/// Tags are read from `.git/refs/tags` and `.git/packed-refs`. A loose
+/// annotated tag refers to a tag object rather than to the commit, so it
+/// is only recognised once git has packed it.
+pub fn git_head_version(repo_dir: &Path) -> Option<Version> {
+    let git_dir = repo_dir.push(".git");
+    let head = match io::read_whole_file_str(&git_dir.push("HEAD")) {
+        Ok(s) => s.trim().to_owned(),
+        Err(_) => return None
+    };
+    let commit = if head.starts_with("ref: ") {
+        match read_git_ref(&git_dir, head.slice_from(5).trim()) {
+            Some(c) => c,
+            None => return None
+        }
+    } else {
+        head
+    };
+    debug2!("git_head_version: HEAD of {} is {}", repo_dir.to_str(), commit);
+
+    let mut best = None;
+    for (name, target) in git_tags(&git_dir).move_iter() {
+        if target != commit {
+            continue;
+        }
+        let version = match try_parsing_version(name) {
+            Some(v) => v,
+            None => match semver::parse(name) {
+                Some(v) => SemanticVersion(v),
+                None => continue
+            }
+        };
+        let newer = match best {
+            Some(ref b) => is_newer_version(&version, b),
+            None => true
+        };
+        if newer {
+            best = Some(version);
+        }
+    }
+    match best {
+        Some(v) => Some(v),
+        None => Some(Tagged(commit))
+    }
+}
+
+/// Returns true if the tag version `a` is higher than `b`. Versions are
+/// compared by their numeric components, so "0.10" is higher than "0.9"
+/// and "1.2" is the same as "1.2.0"; of two equal ones, a release is higher
+/// than a pre-release.
+fn is_newer_version(a: &Version, b: &Version) -> bool {
+    fn components(v: &Version) -> ~[uint] {
+        let mut parts: ~[uint] = match *v {
+            ExactRevision(ref s) => s.split_iter('.').filter_map(|p| from_str(p)).collect(),
+            SemanticVersion(ref v) => ~[v.major, v.minor, v.patch],
+            _ => ~[]
+        };
+        while parts.last_opt() == Some(&0) {
+            parts.pop();
+        }
+        parts
+    }
+    let (ca, cb) = (components(a), components(b));
+    if ca != cb {
+        return ca > cb;
+    }
+    match (a, b) {
+        (&SemanticVersion(ref va), &SemanticVersion(ref vb)) => va > vb,
+        (&ExactRevision(_), &SemanticVersion(ref vb)) => !vb.pre.is_empty(),
+        _ => false
+    }
+}
+
+/// Returns the commit hash that the ref `name`, such as "refs/heads/master",
+/// points to, looking first for a loose ref file and then in packed-refs.
+fn read_git_ref(git_dir: &Path, name: &str) -> Option<~str> {
+    match io::read_whole_file_str(&git_dir.push_rel(&Path(name))) {
+        Ok(s) => return Some(s.trim().to_owned()),
+        Err(_) => ()
+    }
+    for (ref_name, target) in packed_git_refs(git_dir).move_iter() {
+        if ref_name.as_slice() == name {
+            return Some(target);
+        }
+    }
+    None
+}
+
+/// Returns the (name, commit hash) pairs of all tags in the repository.
+fn git_tags(git_dir: &Path) -> ~[(~str, ~str)] {
+    let mut tags = ~[];
+    let tag_dir = git_dir.push_many(["refs", "tags"]);
+    for name in os::list_dir(&tag_dir).iter() {
+        match io::read_whole_file_str(&tag_dir.push(*name)) {
+            Ok(s) => tags.push((name.clone(), s.trim().to_owned())),
+            Err(_) => ()
+        }
+    }
+    for (ref_name, target) in packed_git_refs(git_dir).move_iter() {
+        if ref_name.starts_with("refs/tags/") {
+            tags.push((ref_name.slice_from(10).to_owned(), target));
+        }
+    }
+    tags
+}
+
+/// Returns the (name, commit hash) pairs listed in `.git/packed-refs`. For
+/// an annotated tag this is the commit it peels to, from the `^` line
+/// following it.
+fn packed_git_refs(git_dir: &Path) -> ~[(~str, ~str)] {
+    let text = match io::read_whole_file_str(&git_dir.push("packed-refs")) {
+        Ok(s) => s,
+        Err(_) => return ~[]
+    };
+    let mut refs: ~[(~str, ~str)] = ~[];
+    for line in text.line_iter() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with("#") {
+            continue;
+        }
+        if line.starts_with("^") {
+            match refs.pop_opt() {
+                Some((name, _)) => refs.push((name, line.slice_from(1).to_owned())),
+                None => ()
+            }
+            continue;
+        }
+        let fields: ~[&str] = line.split_iter(' ').collect();
+        if fields.len() == 2 {
+            refs.push((fields[1].to_owned(), fields[0].to_owned()));
+        }
+    }
+    refs
+}
+
 /// If `remote_path` refers to a git repo that can be downloaded,
 /// and the most recent tag in that repo denotes a version, return it;
 /// otherwise, `None`
@@ -251,3 +460,48 @@ fn test_split_version() {
     assert!(split_version(s) == Some((s.slice(0, 1), ExactRevision(~"1.2"))));
     assert!(split_version("a#a#3.4") == None);
 }
+
+#[test]
+fn test_version_to_str_round_trip() {
+    for s in ["1", "1.2", "1.2.0", "1.0.17", "0.1", "1.2.3-rc.1", "1.2.3+build.5",
+              "1.2.3-alpha+001", "beta", "v1.2"].iter() {
+        let v = Version::parse(*s).expect(format!("couldn't parse {}", *s));
+        assert_eq!(v.to_str(), (*s).to_owned());
+        assert_eq!(Version::parse(v.to_str()), Some(v));
+    }
+
+    // No trailing ".0" components are invented.
+    assert_eq!(Version::parse("1.2").unwrap().to_str(), ~"1.2");
+    assert!(Version::parse("1.2") != Version::parse("1.2.0"));
+
+    match Version::parse(" 1.2.3-rc.1 ") {
+        Some(SemanticVersion(ref v)) => assert_eq!(v.pre.len(), 2),
+        v => fail2!("expected a semantic version, got {:?}", v)
+    }
+    match Version::parse("beta") {
+        Some(Tagged(ref s)) => assert_eq!(*s, ~"beta"),
+        v => fail2!("expected a tag, got {:?}", v)
+    }
+    assert!(Version::parse("").is_none());
+    assert!(Version::parse("   ").is_none());
+}
+
+#[test]
+fn test_version_serialization() {
+    use extra::json;
+    use std::io;
+
+    for s in ["1.2", "1.0.17", "1.2.3-rc.1", "beta"].iter() {
+        let v: Version = from_str(*s).unwrap();
+        let encoded = do io::with_str_writer |wr| {
+            let mut encoder = json::Encoder(wr);
+            v.encode(&mut encoder);
+        };
+        assert_eq!(encoded, format!("\"{}\"", *s));
+
+        let mut decoder = json::Decoder(json::from_str(encoded).unwrap());
+        let decoded: Version = Decodable::decode(&mut decoder);
+        assert_eq!(decoded, v);
+    }
+    assert!(from_str::<Version>("").is_none());
+}
@@ -0,0 +1,95 @@
+// Copyright 2013 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Shuffling a standard 52-card deck
+
+use iter::range;
+use rand::Rng;
+
+/// The number of ranks in each suit.
+pub static NUM_RANKS: u8 = 13;
+/// The number of suits in a deck.
+pub static NUM_SUITS: u8 = 4;
+
+/// A playing card. Ranks run from 0 to 12 and suits from 0 to 3; what they
+/// mean (for instance whether rank 0 is a two or an ace) is up to the game.
+#[deriving(Clone, Eq)]
+pub struct Card {
+    /// The rank of the card, in `[0, 13)`.
+    rank: u8,
+    /// The suit of the card, in `[0, 4)`.
+    suit: u8,
+}
+
+/// Returns the 52 cards of a deck in order: every rank of suit 0, then every
+/// rank of suit 1, and so on.
+pub fn new_deck() -> ~[Card] {
+    let mut deck = ~[];
+    for suit in range(0, NUM_SUITS) {
+        for rank in range(0, NUM_RANKS) {
+            deck.push(Card { rank: rank, suit: suit });
+        }
+    }
+    deck
+}
+
+/// Returns a deck of 52 cards in random order, Fisher-Yates shuffled by
+/// `rng`. Generators constructed with the same seed deal the same deck.
+///
+/// # Example
+///
+/// ```rust
+/// use std::rand;
+/// use std::rand::cards;
+///
+/// fn main() {
+///     let deck = cards::shuffle_deck(&mut rand::rng());
+///     println!("the top card is {:?}", deck[0]);
+/// }
+/// ```
+pub fn shuffle_deck<R: Rng>(rng: &mut R) -> ~[Card] {
+    rng.shuffle(new_deck())
+}
+
+#[cfg(test)]
+mod test {
+    use iter::range;
+    use rand::*;
+    use super::*;
+
+    #[test]
+    fn test_new_deck() {
+        let deck = new_deck();
+        assert_eq!(deck.len(), 52);
+        assert_eq!(deck[0], Card { rank: 0, suit: 0 });
+        assert_eq!(deck[51], Card { rank: 12, suit: 3 });
+    }
+
+    #[test]
+    fn test_shuffle_deck() {
+        let mut r = rng();
+        let deck = shuffle_deck(&mut r);
+        assert_eq!(deck.len(), 52);
+        for i in range(0u, 52) {
+            assert!(deck[i].rank < NUM_RANKS && deck[i].suit < NUM_SUITS);
+            for j in range(0u, i) {
+                assert!(deck[i] != deck[j]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_shuffle_deck_seeded() {
+        let seed = [1u8, 2, 3, 4];
+        let mut ra = IsaacRng::new_seeded(seed);
+        let mut rb = IsaacRng::new_seeded(seed);
+        assert_eq!(shuffle_deck(&mut ra), shuffle_deck(&mut rb));
+    }
+}
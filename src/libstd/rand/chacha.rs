@@ -0,0 +1,262 @@
+// Copyright 2013 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The ChaCha random number generator.
+
+use iter::range;
+use rand::{Rng, SeedableRng};
+use rand;
+
+static KEY_WORDS: uint = 8;
+static STATE_WORDS: uint = 16;
+static CHACHA_ROUNDS: uint = 20;
+
+/// A random number generator that uses the ChaCha20 stream cipher, as
+/// described by [RFC 7539](http://tools.ietf.org/html/rfc7539).
+///
+/// The generator returns the keystream for its key, a word at a time. It
+/// uses the original layout of the state, with a 64-bit block counter and a
+/// 64-bit nonce, which both start at 0. The keystream matches RFC 7539's
+/// for any counter below 2^32 and nonce whose low word is 0.
+///
+/// The ChaCha algorithm is suitable for cryptographic purposes.
+pub struct ChaChaRng {
+    priv buffer: [u32, .. STATE_WORDS],
+    priv state: [u32, .. STATE_WORDS],
+    priv index: uint,
+}
+
+macro_rules! quarter_round(
+    ($a:expr, $b:expr, $c:expr, $d:expr) => {{
+        $a += $b; $d ^= $a; $d = ($d << 16) | ($d >> 16);
+        $c += $d; $b ^= $c; $b = ($b << 12) | ($b >> 20);
+        $a += $b; $d ^= $a; $d = ($d << 8) | ($d >> 24);
+        $c += $d; $b ^= $c; $b = ($b << 7) | ($b >> 25);
+    }}
+)
+
+macro_rules! double_round(
+    ($x:expr) => {{
+        // Column round
+        quarter_round!($x[0], $x[4], $x[ 8], $x[12]);
+        quarter_round!($x[1], $x[5], $x[ 9], $x[13]);
+        quarter_round!($x[2], $x[6], $x[10], $x[14]);
+        quarter_round!($x[3], $x[7], $x[11], $x[15]);
+        // Diagonal round
+        quarter_round!($x[0], $x[5], $x[10], $x[15]);
+        quarter_round!($x[1], $x[6], $x[11], $x[12]);
+        quarter_round!($x[2], $x[7], $x[ 8], $x[13]);
+        quarter_round!($x[3], $x[4], $x[ 9], $x[14]);
+    }}
+)
+
+impl ChaChaRng {
+    /// Create a ChaCha random number generator with a random key.
+    pub fn new() -> ChaChaRng {
+        let bytes = rand::seed();
+        let mut key = [0u32, .. KEY_WORDS];
+        for i in range(0u, KEY_WORDS) {
+            for j in range(0u, 4) {
+                key[i] |= (bytes[4 * i + j] as u32) << (8 * j);
+            }
+        }
+        SeedableRng::from_seed(key)
+    }
+
+    /// Create a ChaCha random number generator with the all-zero key.
+    pub fn new_unseeded() -> ChaChaRng {
+        SeedableRng::from_seed([0u32, .. KEY_WORDS])
+    }
+
+    /// Set the block counter, so that the next word returned is the first
+    /// word of block `counter` of the keystream.
+    pub fn set_counter(&mut self, counter: u64) {
+        self.state[12] = counter as u32;
+        self.state[13] = (counter >> 32) as u32;
+        self.index = STATE_WORDS;
+    }
+
+    /// Set the nonce, and restart the keystream at the current block.
+    pub fn set_nonce(&mut self, nonce: u64) {
+        self.state[14] = nonce as u32;
+        self.state[15] = (nonce >> 32) as u32;
+        self.index = STATE_WORDS;
+    }
+
+    /// Fill `buffer` with the next block of keystream, and advance the
+    /// block counter.
+    fn update(&mut self) {
+        let mut x = self.state;
+        do (CHACHA_ROUNDS / 2).times {
+            double_round!(x);
+        }
+        for i in range(0u, STATE_WORDS) {
+            self.buffer[i] = x[i] + self.state[i];
+        }
+        self.index = 0;
+
+        self.state[12] += 1;
+        if self.state[12] == 0 {
+            self.state[13] += 1;
+        }
+    }
+}
+
+// Fixed length vectors don't implement Clone, so this can't be derived.
+impl Clone for ChaChaRng {
+    /// Returns a generator at the same point of the same keystream as
+    /// `self`.
+    fn clone(&self) -> ChaChaRng {
+        *self
+    }
+}
+
+impl Rng for ChaChaRng {
+    #[inline]
+    fn next(&mut self) -> u32 {
+        if self.index == STATE_WORDS {
+            self.update();
+        }
+        let value = self.buffer[self.index];
+        self.index += 1;
+        value
+    }
+}
+
+impl SeedableRng<[u32, .. KEY_WORDS]> for ChaChaRng {
+    /// Use `seed` as the key, and restart the keystream at block 0 with a
+    /// zero nonce.
+    fn reseed(&mut self, seed: [u32, .. KEY_WORDS]) {
+        // "expand 32-byte k"
+        self.state[0] = 0x61707865;
+        self.state[1] = 0x3320646e;
+        self.state[2] = 0x79622d32;
+        self.state[3] = 0x6b206574;
+        for i in range(0u, KEY_WORDS) {
+            self.state[4 + i] = seed[i];
+        }
+        self.set_nonce(0);
+        self.set_counter(0);
+    }
+
+    /// Create a new generator keyed with `seed`.
+    fn from_seed(seed: [u32, .. KEY_WORDS]) -> ChaChaRng {
+        let mut rng = ChaChaRng {
+            buffer: [0, .. STATE_WORDS],
+            state: [0, .. STATE_WORDS],
+            index: STATE_WORDS
+        };
+        rng.reseed(seed);
+        rng
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use iter::range;
+    use rand::*;
+    use super::*;
+    use vec;
+
+    fn next_block(rng: &mut ChaChaRng) -> ~[u32] {
+        vec::from_fn(16, |_| rng.next())
+    }
+
+    #[test]
+    fn test_chacha_rfc_7539_zero_key() {
+        // RFC 7539 appendix A.1, test vectors 1 and 2: consecutive blocks
+        // of the all-zero key.
+        let mut rng = ChaChaRng::new_unseeded();
+        assert_eq!(next_block(&mut rng),
+                   ~[0xade0b876, 0x903df1a0, 0xe56a5d40, 0x28bd8653,
+                     0xb819d2bd, 0x1aed8da0, 0xccef36a8, 0xc70d778b,
+                     0x7c5941da, 0x8d485751, 0x3fe02477, 0x374ad8b8,
+                     0xf4b8436a, 0x1ca11815, 0x69b687c3, 0x8665eeb2]);
+        assert_eq!(next_block(&mut rng),
+                   ~[0xbee7079f, 0x7a385155, 0x7c97ba98, 0x0d082d73,
+                     0xa0290fcb, 0x6965e348, 0x3e53c612, 0xed7aee32,
+                     0x7621b729, 0x434ee69c, 0xb03371d5, 0xd539d874,
+                     0x281fed31, 0x45fb0a51, 0x1f0ae1ac, 0x6f4d794b]);
+    }
+
+    #[test]
+    fn test_chacha_rfc_7539_keys_and_nonces() {
+        // RFC 7539 appendix A.1, test vector 3
+        let mut key = [0u32, .. 8];
+        key[7] = 0x01000000;
+        let mut rng: ChaChaRng = SeedableRng::from_seed(key);
+        rng.set_counter(1);
+        assert_eq!(next_block(&mut rng),
+                   ~[0x2452eb3a, 0x9249f8ec, 0x8d829d9b, 0xddd4ceb1,
+                     0xe8252083, 0x60818b01, 0xf38422b8, 0x5aaa49c9,
+                     0xbb00ca8e, 0xda3ba7b4, 0xc4b592d1, 0xfdf2732f,
+                     0x4436274e, 0x2561b3c8, 0xebdd4aa6, 0xa0136c00]);
+
+        // test vector 4
+        let mut key = [0u32, .. 8];
+        key[0] = 0x0000ff00;
+        rng.reseed(key);
+        rng.set_counter(2);
+        assert_eq!(next_block(&mut rng),
+                   ~[0xfb4dd572, 0x4bc42ef1, 0xdf922636, 0x327f1394,
+                     0xa78dea8f, 0x5e269039, 0xa1bebbc1, 0xcaf09aae,
+                     0xa25ab213, 0x48a6b46c, 0x1b9d9bcb, 0x092c5be6,
+                     0x546ca624, 0x1bec45d5, 0x87f47473, 0x96f0992e]);
+
+        // test vector 5
+        rng.reseed([0u32, .. 8]);
+        rng.set_nonce(0x02000000_00000000);
+        assert_eq!(next_block(&mut rng),
+                   ~[0x374dc6c2, 0x3736d58c, 0xb904e24a, 0xcd3f93ef,
+                     0x88228b1a, 0x96a4dfb3, 0x5b76ab72, 0xc727ee54,
+                     0x0e0e978a, 0xf3145c95, 0x1b748ea8, 0xf786c297,
+                     0x99c28f5f, 0x628314e8, 0x398a19fa, 0x6ded1b53]);
+    }
+
+    #[test]
+    fn test_chacha_rfc_7539_block_function() {
+        // RFC 7539 section 2.3.2. The RFC's 96-bit nonce 0x09000000,
+        // 0x4a000000, 0x00000000 with block count 1 is the same state as a
+        // 64-bit counter whose high word is 0x09000000.
+        let key = [0x03020100, 0x07060504, 0x0b0a0908, 0x0f0e0d0c,
+                   0x13121110, 0x17161514, 0x1b1a1918, 0x1f1e1d1c];
+        let mut rng: ChaChaRng = SeedableRng::from_seed(key);
+        rng.set_counter(0x09000000_00000001);
+        rng.set_nonce(0x4a000000);
+        assert_eq!(next_block(&mut rng),
+                   ~[0xe4e7f110, 0x15593bd1, 0x1fdd0f50, 0xc47120a3,
+                     0xc7f4d1c7, 0x0368c033, 0x9aaa2204, 0x4e6cd4c3,
+                     0x466482d2, 0x09aa9f07, 0x05d7c214, 0xa2028bd9,
+                     0xd19c12b5, 0xb94e16de, 0xe883d0cb, 0x4e3c50a2]);
+    }
+
+    #[test]
+    fn test_chacha_reseed() {
+        let key = [1u32, 2, 3, 4, 5, 6, 7, 8];
+        let mut ra: ChaChaRng = SeedableRng::from_seed(key);
+        let first = vec::from_fn(100, |_| ra.next());
+        ra.reseed(key);
+        assert_eq!(vec::from_fn(100, |_| ra.next()), first);
+
+        let mut rb = ChaChaRng::new();
+        let mut rc = ChaChaRng::new();
+        assert!(range(0, 16).any(|_| rb.next() != rc.next()));
+    }
+
+    #[test]
+    fn test_chacha_counter_carry() {
+        let mut ra = ChaChaRng::new_unseeded();
+        ra.set_counter(0xffffffff);
+        next_block(&mut ra);
+        let mut rb = ChaChaRng::new_unseeded();
+        rb.set_counter(0x1_00000000);
+        assert_eq!(next_block(&mut ra), next_block(&mut rb));
+    }
+}
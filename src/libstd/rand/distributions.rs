@@ -20,11 +20,97 @@
 // Generating Random Variables"], but more robust. If one wanted, one
 // could implement VIZIGNOR the ZIGNOR paper for more speed.
 
+use f64;
 use num;
-use rand::{Rng,Rand};
+use num::{Orderable, RealExt};
+use u64;
+use vec;
+use rand::{Rng,Rand,Weighted};
 
 mod ziggurat_tables;
 
+/// Types that can be used to draw values of type `Support` from a
+/// probability distribution with fixed parameters.
+pub trait Distribution<Support> {
+    /// Draw a value from the distribution, using `rng` as the source of
+    /// randomness.
+    fn sample<R: Rng>(&self, rng: &mut R) -> Support;
+}
+
+/// Integers drawn uniformly from the half-open interval `[low, high)`.
+///
+/// This is what `Rng::gen_integer_range` samples from, but the width of
+/// the interval and the rejection zone that keeps draws unbiased are worked
+/// out once, when the `Range` is built, rather than on every draw.
+///
+/// # Example
+///
+/// ```
+/// use std::rand;
+/// use std::rand::distributions::{Range, Distribution};
+///
+/// fn main() {
+///     let die = Range::new(1, 7);
+///     let mut rng = rand::task_rng();
+///     let mut sum = 0;
+///     for _ in range(0, 1000) {
+///         sum += die.sample(&mut rng);
+///     }
+///     println!("{}", sum);
+/// }
+/// ```
+pub struct Range<T> {
+    priv low: T,
+    // The number of values in the interval, modulo 2^64, so 0 means all of
+    // a 64-bit type.
+    priv range: u64,
+    // Draws at or above this are rejected, so that every value is
+    // equally likely.
+    priv accept_zone: u64,
+}
+
+impl<T: Rand + Int> Range<T> {
+    /// Construct a new `Range` over `[low, high)`. Fails if `low >= high`.
+    pub fn new(low: T, high: T) -> Range<T> {
+        assert!(low < high, "Range::new called with low >= high");
+        Range::with_width(low, high.to_u64() - low.to_u64())
+    }
+
+    /// Construct a new `Range` over the closed interval `[low, high]`,
+    /// which may include `T`'s maximum value. Fails if `low > high`.
+    pub fn new_inclusive(low: T, high: T) -> Range<T> {
+        assert!(low <= high, "Range::new_inclusive called with low > high");
+        Range::with_width(low, high.to_u64() - low.to_u64() + 1)
+    }
+
+    // `range` is the difference of the bounds taken modulo 2^64, which is
+    // exact for any interval of a type of at most 64 bits, even one
+    // spanning zero, except that the whole of a 64-bit type wraps to 0.
+    fn with_width(low: T, range: u64) -> Range<T> {
+        let accept_zone = if range == 0 {
+            u64::max_value
+        } else {
+            u64::max_value - u64::max_value % range
+        };
+        Range { low: low, range: range, accept_zone: accept_zone }
+    }
+}
+
+impl<T: Rand + Int> Distribution<T> for Range<T> {
+    fn sample<R: Rng>(&self, rng: &mut R) -> T {
+        if self.range == 0 {
+            // Every value of the type is in range.
+            return rng.gen();
+        }
+        loop {
+            let rand = rng.gen::<u64>();
+            if rand < self.accept_zone {
+                return self.low + NumCast::from(rand % self.range);
+            }
+        }
+    }
+}
+
 // inlining should mean there is no performance penalty for this
 #[inline]
 fn ziggurat<R:Rng>(rng: &mut R,
@@ -110,6 +196,161 @@ impl Rand for StandardNormal {
     }
 }
 
+/// The normal distribution `N(mean, std_dev**2)`, sampled via
+/// `StandardNormal`.
+///
+/// # Example
+///
+/// ```
+/// use std::rand;
+/// use std::rand::distributions::{Normal, Distribution};
+///
+/// fn main() {
+///     let normal = Normal::new(2.0, 3.0);
+///     let v = normal.sample(&mut rand::task_rng());
+///     println!("{} is from a N(2, 9) distribution", v)
+/// }
+/// ```
+pub struct Normal {
+    priv mean: f64,
+    priv std_dev: f64,
+}
+
+impl Normal {
+    /// Construct a new `Normal` distribution with the given mean and
+    /// standard deviation. Fails if `std_dev < 0`.
+    pub fn new(mean: f64, std_dev: f64) -> Normal {
+        assert!(std_dev >= 0.0, "Normal::new called with `std_dev` < 0");
+        Normal { mean: mean, std_dev: std_dev }
+    }
+}
+
+impl Distribution<f64> for Normal {
+    fn sample<R: Rng>(&self, rng: &mut R) -> f64 {
+        self.mean + self.std_dev * (*rng.gen::<StandardNormal>())
+    }
+}
+
+/// The standard normal cumulative distribution function.
+fn std_normal_cdf(x: f64) -> f64 {
+    0.5 * f64::erfc(-x / f64::consts::sqrt2)
+}
+
+/// The inverse of `std_normal_cdf` for `p` in `(0, 1)`: Acklam's rational
+/// approximation refined by one step of Halley's method, which brings it
+/// close to full precision even deep in the lower tail.
+fn inv_std_normal_cdf(p: f64) -> f64 {
+    static A: [f64, ..6] = [-3.969683028665376e+01, 2.209460984245205e+02,
+                            -2.759285104469687e+02, 1.383577518672690e+02,
+                            -3.066479806614716e+01, 2.506628277459239e+00];
+    static B: [f64, ..5] = [-5.447609879822406e+01, 1.615858368580409e+02,
+                            -1.556989798598866e+02, 6.680131188771972e+01,
+                            -1.328068155288572e+01];
+    static C: [f64, ..6] = [-7.784894002430293e-03, -3.223964580411365e-01,
+                            -2.400758277161838e+00, -2.549732539343734e+00,
+                            4.374664141464968e+00, 2.938163982698783e+00];
+    static D: [f64, ..4] = [7.784695709041462e-03, 3.224671290700398e-01,
+                            2.445134137142996e+00, 3.754408661907416e+00];
+    static P_LOW: f64 = 0.02425;
+
+    let tail = |q: f64| {
+        (((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5]) /
+            ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.0)
+    };
+    let x = if p < P_LOW {
+        tail((-2.0 * p.ln()).sqrt())
+    } else if p <= 1.0 - P_LOW {
+        let q = p - 0.5;
+        let r = q * q;
+        (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q /
+            (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.0)
+    } else {
+        -tail((-2.0 * (1.0 - p).ln()).sqrt())
+    };
+
+    let e = std_normal_cdf(x) - p;
+    let u = e * (2.0 * f64::consts::pi).sqrt() * (x * x / 2.0).exp();
+    x - u / (1.0 + x * u / 2.0)
+}
+
+/// The normal distribution `N(mean, std_dev**2)` restricted to `[lo, hi]`:
+/// samples outside the bounds are redrawn rather than moved onto them, so
+/// unlike `Clamped` no probability mass piles up at the bounds.
+///
+/// When at least a quarter of the normal's mass lies inside the bounds,
+/// samples are drawn by rejection. Otherwise, for instance when the bounds
+/// are far out in a tail, rejection could loop for a very long time, so
+/// the normal CDF is inverted over the interval instead.
+///
+/// # Example
+///
+/// ```
+/// use std::rand;
+/// use std::rand::distributions::{TruncatedNormal, Distribution};
+///
+/// fn main() {
+///     let height = TruncatedNormal::new(170.0, 10.0, 140.0, 210.0);
+///     println!("{} cm", height.sample(&mut rand::task_rng()))
+/// }
+/// ```
+pub struct TruncatedNormal {
+    priv mean: f64,
+    priv std_dev: f64,
+    // The bounds in standard deviations from the mean.
+    priv lo: f64,
+    priv hi: f64,
+    priv rejection: bool,
+}
+
+impl TruncatedNormal {
+    /// Construct a new `TruncatedNormal` distribution. Fails unless
+    /// `std_dev > 0` and `lo < hi`.
+    pub fn new(mean: f64, std_dev: f64, lo: f64, hi: f64) -> TruncatedNormal {
+        assert!(std_dev > 0.0, "TruncatedNormal::new called with `std_dev` <= 0");
+        assert!(lo < hi, "TruncatedNormal::new called with `lo` >= `hi`");
+        let lo = (lo - mean) / std_dev;
+        let hi = (hi - mean) / std_dev;
+        TruncatedNormal {
+            mean: mean,
+            std_dev: std_dev,
+            lo: lo,
+            hi: hi,
+            rejection: std_normal_cdf(hi) - std_normal_cdf(lo) >= 0.25
+        }
+    }
+}
+
+impl Distribution<f64> for TruncatedNormal {
+    fn sample<R: Rng>(&self, rng: &mut R) -> f64 {
+        if self.rejection {
+            loop {
+                let x = *rng.gen::<StandardNormal>();
+                if x >= self.lo && x <= self.hi {
+                    return self.mean + self.std_dev * x;
+                }
+            }
+        }
+
+        // The CDF is only accurate close to 0, so work in the lower tail,
+        // mirroring an interval that lies above the mean.
+        let (lo, hi, sign) = if self.lo > 0.0 {
+            (-self.hi, -self.lo, -1.0)
+        } else {
+            (self.lo, self.hi, 1.0)
+        };
+        let p_lo = std_normal_cdf(lo);
+        let p_hi = std_normal_cdf(hi);
+        let x = if p_hi > 0.0 {
+            inv_std_normal_cdf(p_lo + (p_hi - p_lo) * open01(rng))
+        } else {
+            // So far out that the CDF underflows; nearly all of the mass is
+            // at the bound closest to the mean.
+            hi
+        };
+        self.mean + self.std_dev * sign * x.clamp(&lo, &hi)
+    }
+}
+
 /// A wrapper around an `f64` to generate Exp(1) random numbers. Dividing by
 /// the desired rate `lambda` will give Exp(lambda) distributed random
 /// numbers.
@@ -148,3 +389,934 @@ impl Rand for Exp1 {
                       pdf, zero_case))
     }
 }
+
+/// Draw a uniform value from the open interval `(0, 1)`, for inverse-CDF
+/// sampling where an endpoint would give an infinite result.
+#[inline]
+fn open01<R: Rng>(rng: &mut R) -> f64 {
+    loop {
+        let u = rng.gen::<f64>();
+        if u > 0.0 && u < 1.0 {
+            return u;
+        }
+    }
+}
+
+/// The Weibull distribution with shape `k` and scale `lambda`, commonly
+/// used to model failure times. Sampled by inverting its CDF: `lambda *
+/// (-ln u)^(1/k)` for `u` uniform on `(0, 1)`.
+///
+/// With `k == 1` this is the exponential distribution with rate
+/// `1/lambda`.
+///
+/// # Example
+///
+/// ```
+/// use std::rand;
+/// use std::rand::distributions::{Weibull, Distribution};
+///
+/// fn main() {
+///     let lifetime = Weibull::new(1.5, 1000.0);
+///     let v = lifetime.sample(&mut rand::task_rng());
+///     println!("failed after {} hours", v)
+/// }
+/// ```
+pub struct Weibull {
+    priv shape: f64,
+    priv scale: f64,
+}
+
+impl Weibull {
+    /// Construct a new `Weibull` distribution with the given shape and
+    /// scale. Fails unless both are positive.
+    pub fn new(shape: f64, scale: f64) -> Weibull {
+        assert!(shape > 0.0, "Weibull::new called with `shape` <= 0");
+        assert!(scale > 0.0, "Weibull::new called with `scale` <= 0");
+        Weibull { shape: shape, scale: scale }
+    }
+}
+
+impl Distribution<f64> for Weibull {
+    fn sample<R: Rng>(&self, rng: &mut R) -> f64 {
+        self.scale * (-open01(rng).ln()).pow(&(1.0 / self.shape))
+    }
+}
+
+/// The gamma distribution `Gamma(k, theta)` with shape `k` and scale
+/// `theta`, whose mean is `k * theta`. Normalizing independent draws with a
+/// common scale gives Dirichlet variates, and `X / (X + Y)` for `X ~
+/// Gamma(a, 1)` and `Y ~ Gamma(b, 1)` is `Beta(a, b)`.
+///
+/// Draws use the squeeze and rejection method of Marsaglia and Tsang ["A
+/// Simple Method for Generating Gamma Variables", 2000] on unit normal
+/// draws. Shapes below 1 are drawn as `Gamma(k + 1)` variates scaled by
+/// `U^(1/k)` for uniform `U`.
+///
+/// # Example
+///
+/// ```
+/// use std::rand;
+/// use std::rand::distributions::{Gamma, Distribution};
+///
+/// fn main() {
+///     let gamma = Gamma::new(2.0, 5.0);
+///     let v = gamma.sample(&mut rand::task_rng());
+///     println!("{} is from a Gamma(2, 5) distribution", v)
+/// }
+/// ```
+pub struct Gamma {
+    priv shape: f64,
+    priv scale: f64,
+}
+
+impl Gamma {
+    /// Construct a new `Gamma` distribution with the given shape and
+    /// scale. Fails unless both are positive.
+    pub fn new(shape: f64, scale: f64) -> Gamma {
+        assert!(shape > 0.0, "Gamma::new called with `shape` <= 0");
+        assert!(scale > 0.0, "Gamma::new called with `scale` <= 0");
+        Gamma { shape: shape, scale: scale }
+    }
+}
+
+// Draws from Gamma(shape, 1) for shape >= 1.
+fn marsaglia_tsang<R: Rng>(rng: &mut R, shape: f64) -> f64 {
+    let d = shape - 1.0 / 3.0;
+    let c = 1.0 / (9.0 * d).sqrt();
+    loop {
+        let mut x;
+        let mut v;
+        loop {
+            x = *rng.gen::<StandardNormal>();
+            v = 1.0 + c * x;
+            if v > 0.0 {
+                break;
+            }
+        }
+        v = v * v * v;
+        let u = open01(rng);
+        let x_sq = x * x;
+        if u < 1.0 - 0.0331 * x_sq * x_sq ||
+           u.ln() < 0.5 * x_sq + d * (1.0 - v + v.ln()) {
+            return d * v;
+        }
+    }
+}
+
+impl Distribution<f64> for Gamma {
+    fn sample<R: Rng>(&self, rng: &mut R) -> f64 {
+        let unit = if self.shape >= 1.0 {
+            marsaglia_tsang(rng, self.shape)
+        } else {
+            marsaglia_tsang(rng, self.shape + 1.0) * open01(rng).pow(&(1.0 / self.shape))
+        };
+        self.scale * unit
+    }
+}
+
+/// The binomial distribution: the number of successes in `n` independent
+/// trials that each succeed with probability `p`.
+///
+/// A draw takes about `min(np, n(1 - p))` steps when that is small, by
+/// counting geometric gaps between successes, and otherwise a constant
+/// expected number of steps by rejection from a Lorentzian envelope
+/// [Press et al., "Numerical Recipes", section 7.3]. So even with `n` in
+/// the billions a draw costs no more than a few logarithms.
+///
+/// # Example
+///
+/// ```
+/// use std::rand;
+/// use std::rand::distributions::{Binomial, Distribution};
+///
+/// fn main() {
+///     let flips = Binomial::new(10_000_000, 0.5);
+///     let heads = flips.sample(&mut rand::task_rng());
+///     println!("{} heads in 10 million flips", heads)
+/// }
+/// ```
+pub struct Binomial {
+    priv n: u64,
+    priv p: f64,
+}
+
+impl Binomial {
+    /// Construct a new `Binomial` distribution over `n` trials with
+    /// success probability `p`. Fails unless `0 <= p <= 1`.
+    pub fn new(n: u64, p: f64) -> Binomial {
+        assert!(p >= 0.0 && p <= 1.0, "Binomial::new called with `p` not in [0, 1]");
+        Binomial { n: n, p: p }
+    }
+}
+
+impl Distribution<u64> for Binomial {
+    fn sample<R: Rng>(&self, rng: &mut R) -> u64 {
+        // Count failures instead when they are the rarer outcome, so the
+        // probability below is at most 1/2.
+        let flipped = self.p > 0.5;
+        let p = if flipped { 1.0 - self.p } else { self.p };
+        let n = self.n as f64;
+        let mean = n * p;
+
+        let successes = if p == 0.0 {
+            0
+        } else if mean < 25.0 {
+            // Sum the geometrically distributed numbers of trials up to
+            // and including each success until they run past `n`.
+            let ln_q = (-p).ln_1p();
+            let mut successes = 0u64;
+            let mut trials = 0.0;
+            loop {
+                trials += (open01(rng).ln() / ln_q).floor() + 1.0;
+                if trials > n {
+                    break;
+                }
+                successes += 1;
+            }
+            successes
+        } else {
+            let (_, ln_n_fact) = (n + 1.0).lgamma();
+            let (ln_p, ln_q) = (p.ln(), (-p).ln_1p());
+            let scale = (2.0 * mean * (1.0 - p)).sqrt();
+            let mut k;
+            loop {
+                // Draw from the Lorentzian centred on the mean, within
+                // the support of the distribution.
+                let mut y;
+                loop {
+                    y = (f64::consts::pi * rng.gen::<f64>()).tan();
+                    k = scale * y + mean;
+                    if k >= 0.0 && k < n + 1.0 {
+                        break;
+                    }
+                }
+                k = k.floor();
+                let (_, ln_k_fact) = (k + 1.0).lgamma();
+                let (_, ln_rest_fact) = (n - k + 1.0).lgamma();
+                let ratio = 1.2 * scale * (1.0 + y * y) *
+                    (ln_n_fact - ln_k_fact - ln_rest_fact + k * ln_p + (n - k) * ln_q).exp();
+                if rng.gen::<f64>() <= ratio {
+                    break;
+                }
+            }
+            k as u64
+        };
+
+        if flipped { self.n - successes } else { successes }
+    }
+}
+
+/// The Poisson distribution `Poisson(lambda)`: the number of events in an
+/// interval during which they happen independently at an average rate of
+/// `lambda`. Sampling takes O(1) expected time however large `lambda` is.
+///
+/// # Example
+///
+/// ```
+/// use std::rand;
+/// use std::rand::distributions::{Poisson, Distribution};
+///
+/// fn main() {
+///     let arrivals = Poisson::new(4.5);
+///     let n = arrivals.sample(&mut rand::task_rng());
+///     println!("{} customers arrived this minute", n)
+/// }
+/// ```
+pub struct Poisson {
+    priv lambda: f64,
+}
+
+impl Poisson {
+    /// Construct a new `Poisson` distribution with mean `lambda`. Fails
+    /// unless `lambda > 0`.
+    pub fn new(lambda: f64) -> Poisson {
+        assert!(lambda > 0.0, "Poisson::new called with `lambda` <= 0");
+        Poisson { lambda: lambda }
+    }
+}
+
+impl Distribution<u64> for Poisson {
+    fn sample<R: Rng>(&self, rng: &mut R) -> u64 {
+        let lambda = self.lambda;
+        if lambda < 12.0 {
+            // Knuth's method: count uniform draws until their product
+            // falls below e^-lambda.
+            let limit = (-lambda).exp();
+            let mut product = rng.gen::<f64>();
+            let mut k = 0u64;
+            while product > limit {
+                product *= rng.gen::<f64>();
+                k += 1;
+            }
+            k
+        } else {
+            let ln_lambda = lambda.ln();
+            let (_, ln_lambda_fact) = (lambda + 1.0).lgamma();
+            let scale = (2.0 * lambda).sqrt();
+            let mut k;
+            loop {
+                // Draw from the Lorentzian centred on lambda, within the
+                // support of the distribution.
+                let mut y;
+                loop {
+                    y = (f64::consts::pi * rng.gen::<f64>()).tan();
+                    k = scale * y + lambda;
+                    if k >= 0.0 {
+                        break;
+                    }
+                }
+                k = k.floor();
+                let (_, ln_k_fact) = (k + 1.0).lgamma();
+                let ratio = 0.9 * (1.0 + y * y) *
+                    (k * ln_lambda - ln_k_fact - lambda * ln_lambda + ln_lambda_fact).exp();
+                if rng.gen::<f64>() <= ratio {
+                    break;
+                }
+            }
+            k as u64
+        }
+    }
+}
+
+/// A table for drawing indices `0..n` in proportion to integer weights,
+/// using Vose's alias method: building the table takes O(n) time and each
+/// draw takes O(1), however many weights there are. The table only deals
+/// in indices, so it can be shared by any number of payload arrays.
+///
+/// The arithmetic is exact, so each index is drawn with probability
+/// exactly its weight over the total.
+///
+/// # Example
+///
+/// ```
+/// use std::rand;
+/// use std::rand::distributions::{AliasTable, Distribution};
+///
+/// fn main() {
+///     let names = ["common", "uncommon", "rare"];
+///     let table = AliasTable::new([70, 25, 5]);
+///     let mut rng = rand::task_rng();
+///     for _ in range(0, 10) {
+///         println!("{}", names[table.sample(&mut rng)]);
+///     }
+/// }
+/// ```
+pub struct AliasTable {
+    // Index `i` is kept if a uniform draw from `[0, total)` is below
+    // `threshold[i]`, and replaced by `alias[i]` otherwise.
+    priv threshold: ~[u64],
+    priv alias: ~[uint],
+    priv total: u64,
+}
+
+impl AliasTable {
+    /// Build a table from the given weights. Fails if there are no weights,
+    /// if they are all 0, or if their total times their number overflows a
+    /// `u64`.
+    pub fn new(weights: &[uint]) -> AliasTable {
+        let n = weights.len();
+        assert!(n > 0, "AliasTable::new called with no weights");
+        let mut total = 0u64;
+        for &w in weights.iter() {
+            assert!(total <= u64::max_value - w as u64, "AliasTable::new: weights overflow");
+            total += w as u64;
+        }
+        assert!(total > 0, "AliasTable::new called with weights that are all 0");
+        assert!(total <= u64::max_value / n as u64, "AliasTable::new: weights overflow");
+
+        // Scale each weight so that the average is `total`; the table then
+        // has `n` columns of height `total`.
+        let mut threshold: ~[u64] = weights.iter().map(|&w| w as u64 * n as u64).collect();
+        let mut alias = vec::from_fn(n, |i| i);
+        let mut small = ~[];
+        let mut large = ~[];
+        for (i, &t) in threshold.iter().enumerate() {
+            if t < total { small.push(i) } else { large.push(i) }
+        }
+        while !small.is_empty() && !large.is_empty() {
+            let s = small.pop();
+            let l = large.pop();
+            // Fill the rest of column `s` from `l`.
+            alias[s] = l;
+            threshold[l] = threshold[l] + threshold[s] - total;
+            if threshold[l] < total { small.push(l) } else { large.push(l) }
+        }
+        // Whatever is left fills its own column exactly.
+        for &i in small.iter().chain(large.iter()) {
+            threshold[i] = total;
+        }
+
+        AliasTable { threshold: threshold, alias: alias, total: total }
+    }
+}
+
+impl Distribution<uint> for AliasTable {
+    fn sample<R: Rng>(&self, rng: &mut R) -> uint {
+        let i = rng.gen_integer_range(0, self.threshold.len());
+        if rng.gen_integer_range(0, self.total) < self.threshold[i] {
+            i
+        } else {
+            self.alias[i]
+        }
+    }
+}
+
+/// A distribution over a fixed set of weighted items, which answers each
+/// draw in O(1) time from an `AliasTable` built once up front, rather than
+/// walking the weights on every draw as `Rng::choose_weighted` does. Each
+/// item is drawn with probability exactly its weight over the total, and
+/// returned by clone.
+///
+/// # Example
+///
+/// ```
+/// use std::rand;
+/// use std::rand::Weighted;
+/// use std::rand::distributions::{WeightedChoice, Distribution};
+///
+/// fn main() {
+///     let wc = WeightedChoice::new([Weighted { weight: 2, item: 'a' },
+///                                   Weighted { weight: 4, item: 'b' },
+///                                   Weighted { weight: 1, item: 'c' }]);
+///     let mut rng = rand::task_rng();
+///     for _ in range(0, 16) {
+///         print!("{}", wc.sample(&mut rng));
+///     }
+/// }
+/// ```
+pub struct WeightedChoice<T> {
+    priv items: ~[T],
+    priv table: AliasTable,
+}
+
+impl<T: Clone> WeightedChoice<T> {
+    /// Build the distribution over `items`. Fails if there are no items,
+    /// if their weights are all 0, or if the total weight times the number
+    /// of items overflows a `u64`.
+    pub fn new(items: &[Weighted<T>]) -> WeightedChoice<T> {
+        assert!(items.iter().any(|w| w.weight > 0),
+                "WeightedChoice::new called with a total weight of 0");
+        let weights: ~[uint] = items.iter().map(|w| w.weight).collect();
+        WeightedChoice {
+            items: items.iter().map(|w| w.item.clone()).collect(),
+            table: AliasTable::new(weights)
+        }
+    }
+}
+
+impl<T: Clone> Distribution<T> for WeightedChoice<T> {
+    fn sample<R: Rng>(&self, rng: &mut R) -> T {
+        self.items[self.table.sample(rng)].clone()
+    }
+}
+
+/// Build the probability and alias columns of Vose's alias method for
+/// floating point weights, scaled so that a column is kept when a uniform
+/// draw from `[0, 1)` is below its probability. Fails if there are no
+/// weights, if any is negative or not finite, or if they are all 0.
+fn float_alias_table(weights: &[f64]) -> (~[f64], ~[uint]) {
+    let n = weights.len();
+    assert!(n > 0, "alias table built with no weights");
+    let mut total = 0.0;
+    for &w in weights.iter() {
+        assert!(w >= 0.0 && w.is_finite(), "alias table built with an invalid weight");
+        total += w;
+    }
+    assert!(total > 0.0 && total.is_finite(), "alias table built with weights that are all 0");
+
+    let mut prob: ~[f64] = weights.iter().map(|&w| w * n as f64 / total).collect();
+    let mut alias = vec::from_fn(n, |i| i);
+    let mut small = ~[];
+    let mut large = ~[];
+    for (i, &p) in prob.iter().enumerate() {
+        if p < 1.0 { small.push(i) } else { large.push(i) }
+    }
+    while !small.is_empty() && !large.is_empty() {
+        let s = small.pop();
+        let l = large.pop();
+        alias[s] = l;
+        prob[l] = prob[l] + prob[s] - 1.0;
+        if prob[l] < 1.0 { small.push(l) } else { large.push(l) }
+    }
+    // Rounding can leave columns in either list; they are full.
+    for &i in small.iter().chain(large.iter()) {
+        prob[i] = 1.0;
+    }
+    (prob, alias)
+}
+
+/// A weighted mixture of distributions: each sample picks a component with
+/// probability proportional to its weight, using the alias method so the
+/// choice takes constant time however many components there are, and then
+/// samples from that component.
+///
+/// The components all have the same type `D`.
+///
+/// # Example
+///
+/// ```
+/// use std::rand;
+/// use std::rand::distributions::{Mixture, Normal, Distribution};
+///
+/// fn main() {
+///     // Heights of a population of two groups.
+///     let heights = Mixture::new(~[(0.6, Normal::new(165.0, 7.0)),
+///                                  (0.4, Normal::new(178.0, 7.5))]);
+///     println!("{}cm", heights.sample(&mut rand::task_rng()))
+/// }
+/// ```
+pub struct Mixture<D> {
+    priv components: ~[D],
+    priv prob: ~[f64],
+    priv alias: ~[uint],
+}
+
+impl<D: Distribution<f64>> Mixture<D> {
+    /// Construct a mixture from `(weight, component)` pairs. The weights
+    /// need not sum to 1. Fails if there are no components, if a weight is
+    /// negative or not finite, or if the weights are all 0.
+    pub fn new(components: ~[(f64, D)]) -> Mixture<D> {
+        let weights: ~[f64] = components.iter().map(|&(w, _)| w).collect();
+        let (prob, alias) = float_alias_table(weights);
+        Mixture {
+            components: components.move_iter().map(|(_, d)| d).collect(),
+            prob: prob,
+            alias: alias,
+        }
+    }
+}
+
+impl<D: Distribution<f64>> Distribution<f64> for Mixture<D> {
+    fn sample<R: Rng>(&self, rng: &mut R) -> f64 {
+        let i = rng.gen_integer_range(0, self.prob.len());
+        let i = if rng.gen::<f64>() < self.prob[i] { i } else { self.alias[i] };
+        self.components[i].sample(rng)
+    }
+}
+
+/// Samples from the distribution `D` and clamps each value into
+/// `[lo, hi]`.
+///
+/// Note that this is not the same as the underlying distribution
+/// restricted to `[lo, hi]`: every sample that would have fallen outside
+/// the bounds is moved onto them, so probability mass piles up at `lo` and
+/// `hi`.
+///
+/// # Example
+///
+/// ```
+/// use std::rand;
+/// use std::rand::distributions::{Clamped, Normal, Distribution};
+///
+/// fn main() {
+///     let percent = Clamped::new(Normal::new(50.0, 30.0), 0.0, 100.0);
+///     println!("{}%", percent.sample(&mut rand::task_rng()))
+/// }
+/// ```
+pub struct Clamped<T, D> {
+    priv inner: D,
+    priv lo: T,
+    priv hi: T,
+}
+
+impl<T: Orderable, D: Distribution<T>> Clamped<T, D> {
+    /// Clamp samples from `inner` into `[lo, hi]`. Fails if `lo > hi`.
+    pub fn new(inner: D, lo: T, hi: T) -> Clamped<T, D> {
+        assert!(lo <= hi, "Clamped::new called with `lo` > `hi`");
+        Clamped { inner: inner, lo: lo, hi: hi }
+    }
+}
+
+impl<T: Orderable, D: Distribution<T>> Distribution<T> for Clamped<T, D> {
+    fn sample<R: Rng>(&self, rng: &mut R) -> T {
+        self.inner.sample(rng).clamp(&self.lo, &self.hi)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use f64;
+    use i64;
+    use iter::range;
+    use num;
+    use rand::*;
+    use super::*;
+
+    #[test]
+    fn test_normal() {
+        let norm = Normal::new(10.0, 10.0);
+        let mut r = rng();
+        for _ in range(0, 1000) {
+            norm.sample(&mut r);
+        }
+    }
+
+    #[test]
+    #[should_fail]
+    fn test_normal_invalid_sd() {
+        Normal::new(10.0, -1.0);
+    }
+
+    #[test]
+    fn test_truncated_normal() {
+        let mut r = rng();
+
+        // Mostly inside the bounds, so sampled by rejection.
+        let tn = TruncatedNormal::new(0.0, 1.0, -1.0, 1.5);
+        let mut near_edges = 0;
+        for _ in range(0, 10000) {
+            let x = tn.sample(&mut r);
+            assert!(x >= -1.0 && x <= 1.5);
+            if x < -0.99 || x > 1.49 { near_edges += 1 }
+        }
+        // About 48 samples are expected within 0.01 of an edge; clamping
+        // would put about 2250 exactly on them.
+        assert!(near_edges < 150);
+
+        // A tail interval holding about 0.13% of the mass, sampled through
+        // the inverse CDF. E[X | 3 < X < 4] is about 3.261.
+        for &(mean, sign) in [(0.0, 1.0), (0.0, -1.0), (5.0, 1.0)].iter() {
+            let (lo, hi) = if sign > 0.0 { (mean + 3.0, mean + 4.0) }
+                           else { (mean - 4.0, mean - 3.0) };
+            let tn = TruncatedNormal::new(mean, 1.0, lo, hi);
+            let mut sum = 0.0;
+            for _ in range(0, 10000) {
+                let x = tn.sample(&mut r);
+                assert!(x >= lo && x <= hi);
+                sum += x;
+            }
+            let mean_offset = sign * (sum / 10000.0 - mean);
+            assert!(num::abs(mean_offset - 3.261) < 0.02);
+        }
+
+        // Bounds too far out for the CDF to represent still give samples
+        // inside them.
+        let tn = TruncatedNormal::new(0.0, 1.0, 50.0, 51.0);
+        for _ in range(0, 100) {
+            let x = tn.sample(&mut r);
+            assert!(x >= 50.0 && x <= 51.0);
+        }
+    }
+
+    #[test]
+    #[should_fail]
+    fn test_truncated_normal_invalid_bounds() {
+        TruncatedNormal::new(0.0, 1.0, 1.0, 1.0);
+    }
+
+    #[test]
+    fn test_weibull() {
+        let weibull = Weibull::new(2.0, 3.0);
+        let mut r = rng();
+        for _ in range(0, 1000) {
+            let x = weibull.sample(&mut r);
+            assert!(x >= 0.0 && x < f64::infinity);
+        }
+    }
+
+    #[test]
+    fn test_weibull_shape_one_is_exponential() {
+        // Weibull(1, scale) is Exp(1/scale), whose mean is `scale`.
+        let weibull = Weibull::new(1.0, 2.0);
+        let mut r = rng();
+        let n = 10000;
+        let mut weibull_sum = 0.0;
+        let mut exp_sum = 0.0;
+        for _ in range(0, n) {
+            weibull_sum += weibull.sample(&mut r);
+            exp_sum += *r.gen::<Exp1>() * 2.0;
+        }
+        let weibull_mean = weibull_sum / n as f64;
+        let exp_mean = exp_sum / n as f64;
+        // The standard error of each mean is 0.02.
+        assert!(num::abs(weibull_mean - 2.0) < 0.1);
+        assert!(num::abs(weibull_mean - exp_mean) < 0.15);
+    }
+
+    #[test]
+    #[should_fail]
+    fn test_weibull_invalid_shape() {
+        Weibull::new(0.0, 1.0);
+    }
+
+    #[test]
+    #[should_fail]
+    fn test_weibull_invalid_scale() {
+        Weibull::new(1.0, -1.0);
+    }
+
+    #[test]
+    fn test_gamma() {
+        let mut r = rng();
+        // Shapes below 1 are boosted; 1 and above are drawn directly.
+        for &(shape, scale) in [(0.3, 2.0), (1.0, 1.0), (2.5, 0.5), (40.0, 3.0)].iter() {
+            let gamma = Gamma::new(shape, scale);
+            let draws = 50000;
+            let mut sum = 0.0;
+            let mut sum_sq = 0.0;
+            for _ in range(0, draws) {
+                let x = gamma.sample(&mut r);
+                assert!(x >= 0.0 && x < f64::infinity);
+                sum += x;
+                sum_sq += x * x;
+            }
+            let mean = sum / draws as f64;
+            let variance = sum_sq / draws as f64 - mean * mean;
+            let expected_variance = shape * scale * scale;
+            assert!(num::abs(mean - shape * scale) <
+                    6.0 * (expected_variance / draws as f64).sqrt());
+            assert!(num::abs(variance / expected_variance - 1.0) < 0.1);
+        }
+    }
+
+    #[test]
+    #[should_fail]
+    fn test_gamma_invalid_shape() {
+        Gamma::new(0.0, 1.0);
+    }
+
+    #[test]
+    fn test_range() {
+        let mut r = rng();
+        let die = Range::new(1u, 7);
+        let mut seen = [false, ..6];
+        for _ in range(0, 1000) {
+            let v = die.sample(&mut r);
+            assert!(v >= 1 && v < 7);
+            seen[v - 1] = true;
+        }
+        assert!(seen.iter().all(|&x| x));
+
+        let wide = Range::new(-100i8, 100);
+        let single = Range::new(-12i, -11);
+        for _ in range(0, 1000) {
+            let v = wide.sample(&mut r);
+            assert!(v >= -100 && v < 100);
+            assert_eq!(single.sample(&mut r), -12);
+        }
+
+        // gen_integer_range draws from the same distribution.
+        let mut a = XorShiftRng::new_seeded(1, 2, 3, 4);
+        let mut b = XorShiftRng::new_seeded(1, 2, 3, 4);
+        let dist = Range::new(3u64, 1000);
+        for _ in range(0, 100) {
+            assert_eq!(dist.sample(&mut a), b.gen_integer_range(3u64, 1000));
+        }
+    }
+
+    #[test]
+    #[should_fail]
+    fn test_range_empty() {
+        Range::new(5i, 5);
+    }
+
+    #[test]
+    fn test_range_inclusive() {
+        let mut r = rng();
+        let byte = Range::new_inclusive(0u8, 255);
+        let single = Range::new_inclusive(-12i, -12);
+        let top = Range::new_inclusive(i64::max_value - 1, i64::max_value);
+        let full = Range::new_inclusive(i64::min_value, i64::max_value);
+        let mut seen_max = false;
+        for _ in range(0, 1000) {
+            seen_max |= byte.sample(&mut r) == 255;
+            assert_eq!(single.sample(&mut r), -12);
+            assert!(top.sample(&mut r) >= i64::max_value - 1);
+            full.sample(&mut r);
+        }
+        assert!(seen_max);
+
+        // gen_integer_range_inclusive draws from the same distribution.
+        let mut a = XorShiftRng::new_seeded(1, 2, 3, 4);
+        let mut b = XorShiftRng::new_seeded(1, 2, 3, 4);
+        let dist = Range::new_inclusive(3u64, 1000);
+        for _ in range(0, 100) {
+            assert_eq!(dist.sample(&mut a), b.gen_integer_range_inclusive(3u64, 1000));
+        }
+    }
+
+    #[test]
+    #[should_fail]
+    fn test_range_inclusive_empty() {
+        Range::new_inclusive(5i, 4);
+    }
+
+    #[test]
+    fn test_binomial() {
+        let mut r = rng();
+        // Both the gap-counting (np < 25) and the rejection regimes, and
+        // p above 1/2, where failures are counted instead.
+        for &(n, p) in [(20u64, 0.1), (30, 0.95), (1000, 0.3), (200, 0.7)].iter() {
+            let binomial = Binomial::new(n, p);
+            let draws = 20000;
+            let mut sum = 0.0;
+            let mut sum_sq = 0.0;
+            for _ in range(0, draws) {
+                let x = binomial.sample(&mut r);
+                assert!(x <= n);
+                sum += x as f64;
+                sum_sq += (x * x) as f64;
+            }
+            let mean = sum / draws as f64;
+            let variance = sum_sq / draws as f64 - mean * mean;
+            let expected_variance = n as f64 * p * (1.0 - p);
+            assert!(num::abs(mean - n as f64 * p) < 6.0 * (expected_variance / draws as f64).sqrt());
+            assert!(num::abs(variance / expected_variance - 1.0) < 0.1);
+        }
+
+        assert_eq!(Binomial::new(0, 0.5).sample(&mut r), 0);
+        assert_eq!(Binomial::new(50, 0.0).sample(&mut r), 0);
+        assert_eq!(Binomial::new(50, 1.0).sample(&mut r), 50);
+    }
+
+    #[test]
+    #[should_fail]
+    fn test_binomial_invalid_p() {
+        Binomial::new(10, 1.5);
+    }
+
+    #[test]
+    fn test_poisson() {
+        let mut r = rng();
+        // Knuth's method below lambda = 12 and rejection above.
+        for &lambda in [0.5, 4.5, 11.9, 12.0, 50.0, 1000.0].iter() {
+            let poisson = Poisson::new(lambda);
+            let draws = 100000;
+            let mut sum = 0.0;
+            let mut sum_sq = 0.0;
+            for _ in range(0, draws) {
+                let x = poisson.sample(&mut r) as f64;
+                sum += x;
+                sum_sq += x * x;
+            }
+            let mean = sum / draws as f64;
+            let variance = sum_sq / draws as f64 - mean * mean;
+            assert!(num::abs(mean - lambda) < 6.0 * (lambda / draws as f64).sqrt());
+            assert!(num::abs(variance / lambda - 1.0) < 0.05);
+        }
+    }
+
+    #[test]
+    #[should_fail]
+    fn test_poisson_invalid_lambda() {
+        Poisson::new(0.0);
+    }
+
+    #[test]
+    fn test_weighted_choice() {
+        let wc = WeightedChoice::new([Weighted { weight: 1, item: 'a' },
+                                      Weighted { weight: 0, item: 'b' },
+                                      Weighted { weight: 3, item: 'c' },
+                                      Weighted { weight: 6, item: 'd' }]);
+        let mut r = rng();
+        let mut counts = [0, ..4];
+        for _ in range(0, 100000) {
+            match wc.sample(&mut r) {
+                'a' => counts[0] += 1,
+                'b' => counts[1] += 1,
+                'c' => counts[2] += 1,
+                'd' => counts[3] += 1,
+                _ => fail!()
+            }
+        }
+        assert_eq!(counts[1], 0);
+        // The standard deviations are at most 155.
+        assert!(num::abs(counts[0] - 10000) < 1000);
+        assert!(num::abs(counts[2] - 30000) < 1000);
+        assert!(num::abs(counts[3] - 60000) < 1000);
+
+        let single = WeightedChoice::new([Weighted { weight: 5, item: ~"only" }]);
+        assert_eq!(single.sample(&mut r), ~"only");
+    }
+
+    #[test]
+    #[should_fail]
+    fn test_weighted_choice_zero_total() {
+        WeightedChoice::new([Weighted { weight: 0, item: 1 },
+                             Weighted { weight: 0, item: 2 }]);
+    }
+
+    #[test]
+    fn test_alias_table() {
+        let weights = [1u, 2, 3, 4, 0, 10];
+        let table = AliasTable::new(weights);
+        let mut r = rng();
+        let mut counts = [0u, ..6];
+        let n = 100000;
+        for _ in range(0, n) {
+            counts[table.sample(&mut r)] += 1;
+        }
+        assert_eq!(counts[4], 0);
+        for (&w, &c) in weights.iter().zip(counts.iter()) {
+            let expected = n as f64 * w as f64 / 20.0;
+            // Within about five standard deviations.
+            assert!(num::abs(c as f64 - expected) <= 5.0 * expected.sqrt() + 1.0);
+        }
+
+        let table = AliasTable::new([0u, 7, 0]);
+        for _ in range(0, 100) {
+            assert_eq!(table.sample(&mut r), 1);
+        }
+    }
+
+    #[test]
+    #[should_fail]
+    fn test_alias_table_all_zero() {
+        AliasTable::new([0u, 0]);
+    }
+
+    #[test]
+    fn test_mixture() {
+        let mixture = Mixture::new(~[(3.0, Normal::new(-10.0, 1.0)),
+                                     (1.0, Normal::new(10.0, 1.0))]);
+        let mut r = rng();
+        let (mut low, mut high) = (0, 0);
+        for _ in range(0, 10000) {
+            let x = mixture.sample(&mut r);
+            // Samples come from one mode or the other, never between.
+            assert!(num::abs(num::abs(x) - 10.0) < 6.0);
+            if x < 0.0 { low += 1 } else { high += 1 }
+        }
+        // 7500 and 2500 expected, with a standard deviation of about 43.
+        assert!(num::abs(low as f64 - 7500.0) < 250.0);
+        assert!(num::abs(high as f64 - 2500.0) < 250.0);
+
+        let one = Mixture::new(~[(0.0, Normal::new(-10.0, 1.0)),
+                                 (0.5, Normal::new(10.0, 1.0))]);
+        for _ in range(0, 100) {
+            assert!(one.sample(&mut r) > 0.0);
+        }
+    }
+
+    #[test]
+    #[should_fail]
+    fn test_mixture_all_zero() {
+        Mixture::new(~[(0.0, Normal::new(0.0, 1.0)), (0.0, Normal::new(1.0, 1.0))]);
+    }
+
+    #[test]
+    #[should_fail]
+    fn test_mixture_negative_weight() {
+        Mixture::new(~[(2.0, Normal::new(0.0, 1.0)), (-1.0, Normal::new(1.0, 1.0))]);
+    }
+
+    #[test]
+    fn test_clamped() {
+        let clamped = Clamped::new(Normal::new(0.0, 10.0), -1.0, 1.0);
+        let mut r = rng();
+        let mut at_bounds = 0;
+        for _ in range(0, 1000) {
+            let x = clamped.sample(&mut r);
+            assert!(x >= -1.0 && x <= 1.0);
+            if x == -1.0 || x == 1.0 { at_bounds += 1 }
+        }
+        // P(|x| > 1) is about 0.92 for N(0, 100)
+        assert!(at_bounds > 800);
+    }
+
+    #[test]
+    #[should_fail]
+    fn test_clamped_invalid_bounds() {
+        Clamped::new(Normal::new(0.0, 1.0), 1.0, -1.0);
+    }
+}
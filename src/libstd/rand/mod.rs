@@ -43,9 +43,14 @@ fn main () {
  ```
 */
 
+use at_vec;
 use cast;
+use char;
 use cmp;
 use container::Container;
+use hash;
+use hash::Streaming;
+use hashmap::HashMap;
 use int;
 use iter::{Iterator, range, range_step};
 use local_data;
@@ -53,11 +58,12 @@ use prelude::*;
 use str;
 use sys;
 use u32;
-use u64;
 use uint;
 use vec;
 use libc::size_t;
 
+pub mod cards;
+pub mod chacha;
 pub mod distributions;
 
 /// A type that can be randomly generated using an Rng
@@ -171,6 +177,20 @@ impl Rand for bool {
     }
 }
 
+impl Rand for char {
+    /// A Unicode scalar value chosen uniformly from all 1,112,064 of them.
+    #[inline]
+    fn rand<R: Rng>(rng: &mut R) -> char {
+        // Draw from the values below the limit minus the surrogate range
+        // 0xD800 to 0xDFFF, then shift those past its start over it.
+        static SURROGATE_START: u32 = 0xD800;
+        static SURROGATE_LEN: u32 = 0x800;
+        let v = rng.gen_integer_range(0u32, char::MAX as u32 + 1 - SURROGATE_LEN);
+        let v = if v >= SURROGATE_START { v + SURROGATE_LEN } else { v };
+        char::from_u32(v).unwrap()
+    }
+}
+
 macro_rules! tuple_impl {
     // use variables to indicate the arity of the tuple
     ($($tyvar:ident),* ) => {
@@ -230,6 +250,44 @@ impl<T: Rand + 'static> Rand for @T {
     fn rand<R: Rng>(rng: &mut R) -> @T { @rng.gen() }
 }
 
+/// The exclusive upper bound on the number of elements in a randomly
+/// generated container; lengths are drawn uniformly from `[0, 16)`.
+static RAND_CONTAINER_LEN: uint = 16;
+
+/// Generates a vector whose length is uniformly distributed over 0 to 15
+/// inclusive (so roughly one in sixteen is empty), filled with random
+/// elements. Use `Rng::gen_vec` to choose the length explicitly.
+impl<T: Rand> Rand for ~[T] {
+    #[inline]
+    fn rand<R: Rng>(rng: &mut R) -> ~[T] {
+        let len = rng.gen_integer_range(0u, RAND_CONTAINER_LEN);
+        rng.gen_vec(len)
+    }
+}
+
+/// Generates a vector whose length is uniformly distributed over 0 to 15
+/// inclusive, filled with random elements.
+impl<T: Rand + 'static> Rand for @[T] {
+    #[inline]
+    fn rand<R: Rng>(rng: &mut R) -> @[T] {
+        let len = rng.gen_integer_range(0u, RAND_CONTAINER_LEN);
+        at_vec::from_fn(len, |_| rng.gen())
+    }
+}
+
+impl<K: Rand + Hash + Eq, V: Rand> Rand for HashMap<K, V> {
+    fn rand<R: Rng>(rng: &mut R) -> HashMap<K, V> {
+        // duplicate keys overwrite each other, so small key types
+        // can end up with fewer entries than were generated
+        let len = rng.gen_integer_range(0u, RAND_CONTAINER_LEN);
+        let mut map = HashMap::with_capacity(len);
+        for _ in range(0, len) {
+            map.insert(rng.gen(), rng.gen());
+        }
+        map
+    }
+}
+
 #[abi = "cdecl"]
 pub mod rustrt {
     use libc::size_t;
@@ -247,6 +305,51 @@ pub struct Weighted<T> {
     item: T,
 }
 
+/// A value with a fractional weight compared to other values, for
+/// `Rng::choose_weighted_float`
+pub struct WeightedFloat<T> {
+    /// The weight of this item, which must not be negative
+    weight: f64,
+    /// The actual item which is being weighted
+    item: T,
+}
+
+/// The words `Rng::gen_mnemonic` draws from: 256 short, distinct English
+/// nouns in alphabetical order, so each word carries 8 bits of entropy. This
+/// is not the BIP-39 word list.
+pub static MNEMONIC_WORDS: &'static [&'static str] = &[
+    "acid", "acorn", "actor", "adult", "agent", "alarm", "album", "alley", "amber",
+    "anchor", "angle", "ankle", "apple", "apron", "arena", "armor", "arrow", "atlas",
+    "attic", "autumn", "badge", "bagel", "baker", "bamboo", "banjo", "barrel", "basin",
+    "beach", "beacon", "beard", "beetle", "bench", "berry", "biscuit", "bison", "blade",
+    "blanket", "blossom", "board", "bottle", "breeze", "brick", "bridge", "broom",
+    "bucket", "buffalo", "butter", "cabin", "cactus", "camel", "candle", "canoe",
+    "canvas", "canyon", "carpet", "castle", "cellar", "chalk", "cherry", "chimney",
+    "circle", "cliff", "clock", "cloud", "clover", "cobra", "cobweb", "comet",
+    "compass", "copper", "coral", "cotton", "crane", "crayon", "cricket", "crown",
+    "crystal", "cupboard", "curtain", "daisy", "dancer", "desert", "diamond", "dolphin",
+    "donkey", "dragon", "drum", "dune", "eagle", "earth", "echo", "elbow", "ember",
+    "emerald", "engine", "falcon", "feather", "fence", "ferry", "fiddle", "field",
+    "finch", "fjord", "flame", "flute", "forest", "fossil", "fountain", "fox", "frost",
+    "galaxy", "garden", "garlic", "giant", "ginger", "glacier", "globe", "goat",
+    "grape", "gravel", "guitar", "hammer", "harbor", "harvest", "hawk", "hazel",
+    "helmet", "hermit", "hill", "honey", "horizon", "horse", "igloo", "island", "ivory",
+    "jacket", "jaguar", "jelly", "jewel", "jungle", "kayak", "kettle", "kitten",
+    "ladder", "lagoon", "lantern", "lemon", "lily", "lion", "lizard", "lobster",
+    "locket", "lotus", "magnet", "mango", "maple", "marble", "meadow", "melon",
+    "mirror", "monkey", "moon", "mountain", "mushroom", "nectar", "needle", "nest",
+    "noodle", "oasis", "ocean", "olive", "onion", "orbit", "orchid", "otter", "owl",
+    "oyster", "paddle", "palace", "panda", "parrot", "peach", "pebble", "pelican",
+    "pepper", "piano", "pillow", "pine", "planet", "plum", "pocket", "pony", "puffin",
+    "puzzle", "quartz", "quill", "rabbit", "radio", "rainbow", "raven", "ribbon",
+    "river", "robin", "rocket", "saddle", "salmon", "sandal", "scarf", "shadow",
+    "shell", "silver", "skate", "sled", "snail", "spider", "sponge", "squirrel", "star",
+    "stone", "sugar", "summit", "sunset", "swan", "table", "teapot", "thunder", "tiger",
+    "timber", "toast", "tomato", "torch", "tower", "tractor", "tulip", "tunnel",
+    "turtle", "umbrella", "valley", "velvet", "violin", "volcano", "wagon", "walnut",
+    "walrus", "whale", "whistle", "willow", "window", "winter", "wizard", "wolf",
+    "yacht", "yarn", "zebra", "zephyr", "zipper"];
+
 /// A random number generator
 pub trait Rng {
     /// Return the next random integer
@@ -290,6 +393,52 @@ pub trait Rng {
         vec::from_fn(len, |_| self.gen())
     }
 
+    /// Return an iterator that yields an endless stream of random values of
+    /// type `T`, drawn lazily as the iterator is advanced.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::rand;
+    /// use std::rand::Rng;
+    ///
+    /// fn main() {
+    ///    let mut rng = rand::rng();
+    ///    let key = rng.gen_iter::<u8>().take(16).collect::<~[u8]>();
+    ///    let evens = rng.gen_iter::<uint>().filter(|x| x % 2 == 0).take(3).collect::<~[uint]>();
+    ///    println!("{:?} {:?}", key, evens);
+    /// }
+    /// ```
+    fn gen_iter<'a, T: Rand>(&'a mut self) -> RandIterator<'a, Self, T> {
+        RandIterator { rng: self }
+    }
+
+    /// Fill `dest` with random bytes. Each call to `next` supplies four
+    /// bytes, least significant first; an empty `dest` draws nothing.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::rand;
+    /// use std::rand::Rng;
+    ///
+    /// fn main() {
+    ///     let mut key = [0u8, .. 16];
+    ///     rand::rng().fill_bytes(key);
+    ///     println!("{:?}", key);
+    /// }
+    /// ```
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        let mut word = 0u32;
+        for (i, byte) in dest.mut_iter().enumerate() {
+            if i % 4 == 0 {
+                word = self.next();
+            }
+            *byte = word as u8;
+            word >>= 8;
+        }
+    }
+
     /// Generate a random primitive integer in the range [`low`,
     /// `high`). Fails if `low >= high`.
     ///
@@ -297,7 +446,8 @@ pub trait Rng {
     /// uniform), even for edge cases like `gen_integer_range(0u8,
     /// 170)`, which a naive modulo operation would return numbers
     /// less than 85 with double the probability to those greater than
-    /// 85.
+    /// 85. To draw many values from the same range, construct a
+    /// `distributions::Range` once and sample from that instead.
     ///
     /// # Example
     ///
@@ -313,15 +463,31 @@ pub trait Rng {
     /// }
     /// ```
     fn gen_integer_range<T: Rand + Int>(&mut self, low: T, high: T) -> T {
+        use rand::distributions::Distribution;
         assert!(low < high, "RNG.gen_integer_range called with low >= high");
-        let range = (high - low).to_u64();
-        let accept_zone = u64::max_value - u64::max_value % range;
-        loop {
-            let rand = self.gen::<u64>();
-            if rand < accept_zone {
-                return low + NumCast::from(rand % range);
-            }
-        }
+        distributions::Range::new(low, high).sample(self)
+    }
+
+    /// Return a random integer in the closed interval `[low, high]`,
+    /// which may include `T`'s maximum value. Fails if `low > high`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::rand;
+    /// use std::rand::Rng;
+    ///
+    /// fn main() {
+    ///    let mut rng = rand::rng();
+    ///    let roll: uint = rng.gen_integer_range_inclusive(1u, 6);
+    ///    let byte: u8 = rng.gen_integer_range_inclusive(0u8, 255);
+    ///    println!("{} {}", roll, byte);
+    /// }
+    /// ```
+    fn gen_integer_range_inclusive<T: Rand + Int>(&mut self, low: T, high: T) -> T {
+        use rand::distributions::Distribution;
+        assert!(low <= high, "RNG.gen_integer_range_inclusive called with low > high");
+        distributions::Range::new_inclusive(low, high).sample(self)
     }
 
     /// Return a bool with a 1 in n chance of true
@@ -341,57 +507,183 @@ pub trait Rng {
         n == 0 || self.gen_integer_range(0, n) == 0
     }
 
-    /// Return a random string of the specified length composed of
-    /// A-Z,a-z,0-9.
+    /// Return a bool that is true with probability `p`. Fails unless
+    /// `0 <= p <= 1`.
     ///
     /// # Example
     ///
     /// ```rust
     /// use std::rand;
+    /// use std::rand::Rng;
     ///
     /// fn main() {
-    ///    println(rand::task_rng().gen_ascii_str(10));
+    ///     let mut rng = rand::rng();
+    ///     println!("{:b}", rng.gen_bool(0.3));
     /// }
     /// ```
-    fn gen_ascii_str(&mut self, len: uint) -> ~str {
-        static GEN_ASCII_STR_CHARSET: &'static [u8] = bytes!("ABCDEFGHIJKLMNOPQRSTUVWXYZ\
-                                                             abcdefghijklmnopqrstuvwxyz\
-                                                             0123456789");
-        let mut s = str::with_capacity(len);
-        for _ in range(0, len) {
-            s.push_char(self.choose(GEN_ASCII_STR_CHARSET) as char)
+    fn gen_bool(&mut self, p: f64) -> bool {
+        assert!(p >= 0.0 && p <= 1.0, "RNG.gen_bool called with `p` not in [0, 1]");
+        // The draw is in [0, 1), so 0 is never true and 1 always is.
+        self.gen::<f64>() < p
+    }
+
+    /// Generate `n` bools that are each true with probability `p`, packed
+    /// into `out`: flip `i` is bit `i % 64` of `out[i / 64]`. Bits of `out`
+    /// past the `n`th are cleared. Fails unless `0 <= p <= 1`, or if `out`
+    /// has fewer than `n` bits.
+    ///
+    /// The flips are made 64 at a time, by comparing 64 uniform draws
+    /// against `p` a binary digit at a time with one random word per digit.
+    /// So each 64 flips take as many words as `p` has binary digits: one
+    /// for `p == 0.5`, two for `0.25` or `0.75`, and at most 64.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::rand;
+    /// use std::rand::Rng;
+    ///
+    /// fn main() {
+    ///     let mut rng = rand::rng();
+    ///     let mut mask = [0u64, .. 4];
+    ///     rng.gen_bools_packed(256, 0.8, mask);
+    ///     println!("{:?}", mask);
+    /// }
+    /// ```
+    fn gen_bools_packed(&mut self, n: uint, p: f64, out: &mut [u64]) {
+        assert!(p >= 0.0 && p <= 1.0, "RNG.gen_bools_packed called with `p` not in [0, 1]");
+        assert!(out.len() >= (n + 63) / 64, "RNG.gen_bools_packed called with `out` too short");
+
+        // The binary digits of `p`, most significant first; `p` has at
+        // most 53 significant digits, so this is exact.
+        let threshold = if p < 1.0 { (p * 18446744073709551616.0) as u64 } else { 0 };
+        // Trailing zero digits can't change any comparison, so skip them.
+        let mut low = threshold;
+        let mut digits = 64u;
+        while low != 0 && low & 1 == 0 {
+            low >>= 1;
+            digits -= 1;
+        }
+
+        for (i, word) in out.mut_iter().enumerate() {
+            if i * 64 >= n {
+                *word = 0;
+                continue;
+            }
+            *word = if p == 1.0 {
+                !0
+            } else if threshold == 0 {
+                0
+            } else {
+                // Work up from the last digit: a lane's draw is below `p`
+                // if it is below at this digit, or equal here and below
+                // at a later one.
+                let mut below = 0u64;
+                let mut t = low;
+                do digits.times {
+                    let r = self.gen::<u64>();
+                    below = if t & 1 == 1 { r | below } else { r & below };
+                    t >>= 1;
+                }
+                below
+            };
+            if n - i * 64 < 64 {
+                *word &= (1 << (n - i * 64) as u64) - 1;
+            }
         }
-        s
     }
 
-    /// Choose an item randomly, failing if `values` is empty.
-    fn choose<T: Clone>(&mut self, values: &[T]) -> T {
-        self.choose_option(values).expect("Rng.choose: `values` is empty").clone()
+    /// Return `n` non-negative weights that sum to 1, for taking a random
+    /// weighted average. The weights are a draw from the flat Dirichlet
+    /// distribution, so every such vector of weights is equally likely;
+    /// normalizing `n` uniform draws instead would favour even weights.
+    /// Fails if `n` is 0.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::rand;
+    /// use std::rand::Rng;
+    ///
+    /// fn main() {
+    ///     let mut rng = rand::rng();
+    ///     let w = rng.gen_weights(3);
+    ///     println!("{}", w[0] * 1.0 + w[1] * 2.0 + w[2] * 3.0);
+    /// }
+    /// ```
+    fn gen_weights(&mut self, n: uint) -> ~[f64] {
+        use rand::distributions::Exp1;
+
+        assert!(n > 0, "RNG.gen_weights called with n == 0");
+        // Normalized exponential draws are uniform on the simplex.
+        let mut weights = vec::from_fn(n, |_| *self.gen::<Exp1>());
+        let total = weights.iter().fold(0.0, |a, &b| a + b);
+        for w in weights.mut_iter() {
+            *w /= total;
+        }
+        weights
     }
 
-    /// Choose `Some(&item)` randomly, returning `None` if values is
-    /// empty.
+    /// Return the number of successes in `n` independent trials that each
+    /// succeed with probability `p`. This is a single draw from
+    /// `distributions::Binomial`, so it does not loop over the trials and
+    /// is fast however large `n` is. Fails unless `0 <= p <= 1`.
     ///
     /// # Example
     ///
     /// ```rust
     /// use std::rand;
+    /// use std::rand::Rng;
     ///
     /// fn main() {
-    ///     println!("{:?}", rand::task_rng().choose_option([1,2,4,8,16,32]));
-    ///     println!("{:?}", rand::task_rng().choose_option([]));
+    ///     let mut rng = rand::rng();
+    ///     println!("{} heads", rng.count_successes(10_000_000, 0.5));
     /// }
     /// ```
-    fn choose_option<'a, T>(&mut self, values: &'a [T]) -> Option<&'a T> {
-        if values.is_empty() {
-            None
+    fn count_successes(&mut self, n: u64, p: f64) -> u64 {
+        use rand::distributions::Distribution;
+        distributions::Binomial::new(n, p).sample(self)
+    }
+
+    /// Return a jittered exponential backoff delay, in milliseconds, for
+    /// retry number `attempt` (counting from 0).
+    ///
+    /// The delay is drawn uniformly from [0, `min(cap_ms, base_ms *
+    /// 2^attempt)`), the "full jitter" strategy. The doubling saturates at
+    /// `cap_ms` rather than overflowing, however large `attempt` is, and the
+    /// result is always 0 if `base_ms` or `cap_ms` is 0.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::rand;
+    /// use std::rand::Rng;
+    ///
+    /// fn main() {
+    ///     let mut rng = rand::rng();
+    ///     for attempt in range(0u, 5) {
+    ///         println!("retrying in {}ms", rng.gen_backoff(attempt, 100, 10000));
+    ///     }
+    /// }
+    /// ```
+    fn gen_backoff(&mut self, attempt: uint, base_ms: u64, cap_ms: u64) -> u64 {
+        let ceiling = if attempt < 64 && base_ms <= cap_ms >> attempt {
+            base_ms << attempt
         } else {
-            Some(&values[self.gen_integer_range(0u, values.len())])
+            cap_ms
+        };
+        if ceiling == 0 {
+            0
+        } else {
+            self.gen_integer_range(0, ceiling)
         }
     }
 
-    /// Choose an item respecting the relative weights, failing if the sum of
-    /// the weights is 0
+    /// Return a pair of standard normal values with correlation `rho`.
+    /// Fails if `rho` is outside `[-1, 1]`.
+    ///
+    /// Given independent standard normals `z1` and `z2` the pair is `(z1,
+    /// rho * z1 + sqrt(1 - rho^2) * z2)`.
     ///
     /// # Example
     ///
@@ -401,18 +693,26 @@ pub trait Rng {
     ///
     /// fn main() {
     ///     let mut rng = rand::rng();
-    ///     let x = [rand::Weighted {weight: 4, item: 'a'},
-    ///              rand::Weighted {weight: 2, item: 'b'},
-    ///              rand::Weighted {weight: 2, item: 'c'}];
-    ///     println!("{}", rng.choose_weighted(x));
+    ///     let (height, weight) = rng.gen_bivariate_normal(0.8);
+    ///     println!("{} {}", 170.0 + 10.0 * height, 70.0 + 15.0 * weight);
     /// }
     /// ```
-    fn choose_weighted<T:Clone>(&mut self, v: &[Weighted<T>]) -> T {
-        self.choose_weighted_option(v).expect("Rng.choose_weighted: total weight is 0")
+    fn gen_bivariate_normal(&mut self, rho: f64) -> (f64, f64) {
+        assert!(rho >= -1.0 && rho <= 1.0,
+                "RNG.gen_bivariate_normal called with `rho` outside [-1, 1]");
+        let z1 = *self.gen::<distributions::StandardNormal>();
+        let z2 = *self.gen::<distributions::StandardNormal>();
+        (z1, rho * z1 + (1.0 - rho * rho).sqrt() * z2)
     }
 
-    /// Choose Some(item) respecting the relative weights, returning none if
-    /// the sum of the weights is 0
+    /// Return a simulated latency in milliseconds, drawn from a log-normal
+    /// distribution with the given median. Fails if `median_ms` is not
+    /// positive or `sigma` is negative.
+    ///
+    /// The sample is `exp(ln(median_ms) + sigma * z)` for a standard normal
+    /// `z`, rounded to the nearest millisecond. Larger values of `sigma` give
+    /// a longer tail of slow requests; `sigma == 0` always returns the
+    /// median.
     ///
     /// # Example
     ///
@@ -422,34 +722,23 @@ pub trait Rng {
     ///
     /// fn main() {
     ///     let mut rng = rand::rng();
-    ///     let x = [rand::Weighted {weight: 4, item: 'a'},
-    ///              rand::Weighted {weight: 2, item: 'b'},
-    ///              rand::Weighted {weight: 2, item: 'c'}];
-    ///     println!("{:?}", rng.choose_weighted_option(x));
+    ///     println!("request took {}ms", rng.gen_latency_ms(120.0, 0.5));
     /// }
     /// ```
-    fn choose_weighted_option<T:Clone>(&mut self, v: &[Weighted<T>])
-                                       -> Option<T> {
-        let mut total = 0u;
-        for item in v.iter() {
-            total += item.weight;
-        }
-        if total == 0u {
-            return None;
-        }
-        let chosen = self.gen_integer_range(0u, total);
-        let mut so_far = 0u;
-        for item in v.iter() {
-            so_far += item.weight;
-            if so_far > chosen {
-                return Some(item.item.clone());
-            }
-        }
-        unreachable!();
+    fn gen_latency_ms(&mut self, median_ms: f64, sigma: f64) -> u64 {
+        assert!(median_ms > 0.0, "RNG.gen_latency_ms called with `median_ms` <= 0");
+        assert!(sigma >= 0.0, "RNG.gen_latency_ms called with negative `sigma`");
+        let z = *self.gen::<distributions::StandardNormal>();
+        (median_ms.ln() + sigma * z).exp().round() as u64
     }
 
-    /// Return a vec containing copies of the items, in order, where
-    /// the weight of the item determines how many copies there are
+    /// Return a random `(latitude, longitude)` point, in degrees, inside the
+    /// given bounding box. Both coordinates are drawn uniformly.
+    ///
+    /// A box with `min_lon > max_lon` is taken to cross the antimeridian, so
+    /// the longitude wraps from `min_lon` through ±180 to `max_lon`. Fails if
+    /// a latitude is outside `[-90, 90]`, a longitude is outside
+    /// `[-180, 180]`, or `min_lat > max_lat`.
     ///
     /// # Example
     ///
@@ -459,40 +748,51 @@ pub trait Rng {
     ///
     /// fn main() {
     ///     let mut rng = rand::rng();
-    ///     let x = [rand::Weighted {weight: 4, item: 'a'},
-    ///              rand::Weighted {weight: 2, item: 'b'},
-    ///              rand::Weighted {weight: 2, item: 'c'}];
-    ///     println!("{}", rng.weighted_vec(x));
+    ///     // Somewhere in Fiji, which straddles the antimeridian.
+    ///     let (lat, lon) = rng.gen_point_in_bbox(-21.0, 177.0, -12.0, -178.0);
+    ///     println!("{}, {}", lat, lon);
     /// }
     /// ```
-    fn weighted_vec<T:Clone>(&mut self, v: &[Weighted<T>]) -> ~[T] {
-        let mut r = ~[];
-        for item in v.iter() {
-            for _ in range(0u, item.weight) {
-                r.push(item.item.clone());
-            }
-        }
-        r
+    fn gen_point_in_bbox(&mut self, min_lat: f64, min_lon: f64,
+                         max_lat: f64, max_lon: f64) -> (f64, f64) {
+        assert!(min_lat >= -90.0 && max_lat <= 90.0 && min_lat <= max_lat,
+                "RNG.gen_point_in_bbox called with invalid latitudes");
+        assert!(min_lon >= -180.0 && min_lon <= 180.0 &&
+                max_lon >= -180.0 && max_lon <= 180.0,
+                "RNG.gen_point_in_bbox called with longitudes outside [-180, 180]");
+        let lat = min_lat + (max_lat - min_lat) * self.gen::<f64>();
+        let lon = if min_lon <= max_lon {
+            min_lon + (max_lon - min_lon) * self.gen::<f64>()
+        } else {
+            let width = (180.0 - min_lon) + (max_lon + 180.0);
+            let lon = min_lon + width * self.gen::<f64>();
+            if lon > 180.0 { lon - 360.0 } else { lon }
+        };
+        (lat, lon)
     }
 
-    /// Shuffle a vec
+    /// Return a random UTC offset in seconds, as used by real time zones:
+    /// a multiple of 15 minutes (900 seconds) between -12:00 and +14:00
+    /// inclusive, chosen uniformly.
     ///
     /// # Example
     ///
     /// ```rust
     /// use std::rand;
+    /// use std::rand::Rng;
     ///
     /// fn main() {
-    ///     println!("{:?}", rand::task_rng().shuffle(~[1,2,3]));
+    ///     let offset = rand::rng().gen_tz_offset_seconds();
+    ///     println!("{} minutes from UTC", offset / 60);
     /// }
     /// ```
-    fn shuffle<T>(&mut self, values: ~[T]) -> ~[T] {
-        let mut v = values;
-        self.shuffle_mut(v);
-        v
+    fn gen_tz_offset_seconds(&mut self) -> i32 {
+        static STEP: i32 = 900;
+        self.gen_integer_range(-43200 / STEP, 50400 / STEP + 1) * STEP
     }
 
-    /// Shuffle a mutable vector in place.
+    /// Return a random string of the specified length composed of
+    /// A-Z,a-z,0-9.
     ///
     /// # Example
     ///
@@ -500,71 +800,909 @@ pub trait Rng {
     /// use std::rand;
     ///
     /// fn main() {
-    ///    let rng = rand::task_rng();
-    ///    let mut y = [1,2,3];
-    ///    rng.shuffle_mut(y);
-    ///    println!("{:?}", y);
-    ///    rng.shuffle_mut(y);
-    ///    println!("{:?}", y);
+    ///    println(rand::task_rng().gen_ascii_str(10));
     /// }
     /// ```
-    fn shuffle_mut<T>(&mut self, values: &mut [T]) {
-        let mut i = values.len();
-        while i >= 2u {
-            // invariant: elements with index >= i have been locked in place.
-            i -= 1u;
-            // lock element i in place.
-            values.swap(i, self.gen_integer_range(0u, i + 1u));
+    fn gen_ascii_str(&mut self, len: uint) -> ~str {
+        static GEN_ASCII_STR_CHARSET: &'static [u8] = bytes!("ABCDEFGHIJKLMNOPQRSTUVWXYZ\
+                                                             abcdefghijklmnopqrstuvwxyz\
+                                                             0123456789");
+        let mut s = str::with_capacity(len);
+        for _ in range(0, len) {
+            s.push_char(self.choose(GEN_ASCII_STR_CHARSET) as char)
         }
+        s
     }
 
-    /// Randomly sample up to `n` elements from an iterator.
+    /// Return a random Rust identifier of between 1 and `max_len`
+    /// characters, for fuzzing code generators. Fails if `max_len` is 0.
+    ///
+    /// The identifier starts with an ASCII letter or underscore and
+    /// continues with letters, digits and underscores. Keywords, including
+    /// reserved ones, and the lone wildcard `_` are never returned; if one
+    /// comes up another identifier is drawn instead.
     ///
     /// # Example
     ///
     /// ```rust
     /// use std::rand;
+    /// use std::rand::Rng;
     ///
     /// fn main() {
-    ///    let rng = rand::task_rng();
-    ///    let sample = rng.sample(range(1, 100), 5);
-    ///    println!("{:?}", sample);
+    ///     let mut rng = rand::rng();
+    ///     println!("let {} = 42;", rng.gen_ident(12));
     /// }
     /// ```
-    fn sample<A, T: Iterator<A>>(&mut self, iter: T, n: uint) -> ~[A] {
-        let mut reservoir : ~[A] = vec::with_capacity(n);
-        for (i, elem) in iter.enumerate() {
-            if i < n {
-                reservoir.push(elem);
-                continue
+    fn gen_ident(&mut self, max_len: uint) -> ~str {
+        static KEYWORDS: &'static [&'static str] = &[
+            "as", "break", "const", "do", "else", "enum", "extern", "false", "fn",
+            "for", "if", "impl", "let", "__log_level", "loop", "match", "mod",
+            "mut", "once", "priv", "pub", "ref", "return", "static", "self",
+            "struct", "super", "true", "trait", "type", "unsafe", "use", "while",
+            "in", "continue", "be", "pure", "yield", "typeof", "alignof",
+            "offsetof", "sizeof"];
+        static IDENT_START_CHARSET: &'static [u8] = bytes!("ABCDEFGHIJKLMNOPQRSTUVWXYZ\
+                                                           abcdefghijklmnopqrstuvwxyz_");
+        static IDENT_CHARSET: &'static [u8] = bytes!("ABCDEFGHIJKLMNOPQRSTUVWXYZ\
+                                                     abcdefghijklmnopqrstuvwxyz\
+                                                     0123456789_");
+        assert!(max_len > 0, "RNG.gen_ident called with `max_len` of 0");
+        loop {
+            let len = self.gen_integer_range(1, max_len + 1);
+            let mut s = str::with_capacity(len);
+            s.push_char(self.choose(IDENT_START_CHARSET) as char);
+            for _ in range(1, len) {
+                s.push_char(self.choose(IDENT_CHARSET) as char);
             }
-
-            let k = self.gen_integer_range(0, i + 1);
-            if k < reservoir.len() {
-                reservoir[k] = elem
+            if s != ~"_" && !KEYWORDS.iter().any(|k| *k == s.as_slice()) {
+                return s;
             }
         }
-        reservoir
     }
-}
 
-/// Create a random number generator with a default algorithm and seed.
-///
-/// It returns the cryptographically-safest `Rng` algorithm currently
-/// available in Rust. If you require a specifically seeded `Rng` for
-/// consistency over time you should pick one algorithm and create the
-/// `Rng` yourself.
-pub fn rng() -> IsaacRng {
-    IsaacRng::new()
-}
-
-/// Create a weak random number generator with a default algorithm and seed.
-///
-/// It returns the fastest `Rng` algorithm currently available in Rust without
-/// consideration for cryptography or security. If you require a specifically
-/// seeded `Rng` for consistency over time you should pick one algorithm and
-/// create the `Rng` yourself.
-pub fn weak_rng() -> XorShiftRng {
+    /// Return `words` words drawn uniformly and independently from
+    /// `MNEMONIC_WORDS`, separated by single spaces, as an easy to read and
+    /// type passphrase. Each word adds 8 bits of entropy.
+    ///
+    /// This is not BIP-39 compatible: the word list is a different, smaller
+    /// one and no checksum word is included, so the result must not be used
+    /// as a wallet seed phrase.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::rand;
+    /// use std::rand::Rng;
+    ///
+    /// fn main() {
+    ///     println!("your recovery phrase is: {}", rand::rng().gen_mnemonic(12));
+    /// }
+    /// ```
+    fn gen_mnemonic(&mut self, words: uint) -> ~str {
+        let chosen = vec::from_fn(words, |_| {
+            MNEMONIC_WORDS[self.gen_integer_range(0, MNEMONIC_WORDS.len())]
+        });
+        chosen.connect(" ")
+    }
+
+    /// Return `byte_len` random bytes encoded as unpadded URL-safe base64
+    /// (RFC 4648, section 5), suitable for opaque tokens and secrets.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::rand;
+    /// use std::rand::Rng;
+    ///
+    /// fn main() {
+    ///     println!("https://example.com/reset?token={}", rand::rng().gen_token(32));
+    /// }
+    /// ```
+    fn gen_token(&mut self, byte_len: uint) -> ~str {
+        static BASE64URL_CHARSET: &'static [u8] = bytes!("ABCDEFGHIJKLMNOPQRSTUVWXYZ\
+                                                         abcdefghijklmnopqrstuvwxyz\
+                                                         0123456789-_");
+        let mut bytes = vec::from_elem(byte_len, 0u8);
+        self.fill_bytes(bytes);
+
+        let mut s = str::with_capacity((byte_len * 4 + 2) / 3);
+        for chunk in bytes.chunk_iter(3) {
+            let mut n = 0u32;
+            for (i, &b) in chunk.iter().enumerate() {
+                n |= (b as u32) << (16 - 8 * i);
+            }
+            // Three bytes give four characters; a short final chunk of one
+            // or two bytes gives two or three.
+            for i in range(0, chunk.len() + 1) {
+                s.push_char(BASE64URL_CHARSET[(n >> (18 - 6 * i)) as uint & 63] as char);
+            }
+        }
+        s
+    }
+
+    /// Return a random North American phone number, formatted as `(NXX)
+    /// NXX-XXXX`, for test fixtures.
+    ///
+    /// As the North American Numbering Plan requires, the area code and the
+    /// exchange both start with a digit from 2 to 9. Neither is an N11 code
+    /// such as 911, which are reserved for services.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::rand;
+    ///
+    /// fn main() {
+    ///    println(rand::task_rng().gen_phone_na());
+    /// }
+    /// ```
+    fn gen_phone_na(&mut self) -> ~str {
+        fn nxx<R: Rng>(rng: &mut R) -> uint {
+            loop {
+                let code = rng.gen_integer_range(200u, 1000);
+                if code % 100 != 11 {
+                    return code;
+                }
+            }
+        }
+        let area = nxx(self);
+        let exchange = nxx(self);
+        let line = self.gen_integer_range(0u, 10000);
+        format!("({}) {}-{:04u}", area, exchange, line)
+    }
+
+    /// Return a random CSV field of up to 15 characters, for fuzzing CSV
+    /// parsers.
+    ///
+    /// The characters are drawn from a small alphabet that includes commas,
+    /// double quotes, spaces and line breaks. Whenever the field contains a
+    /// comma, quote or line break it is quoted as RFC 4180 requires: wrapped
+    /// in double quotes with any embedded quotes doubled. The result is
+    /// always a single valid CSV field.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::rand;
+    ///
+    /// fn main() {
+    ///    let rng = rand::task_rng();
+    ///    println(rng.gen_csv_field() + "," + rng.gen_csv_field());
+    /// }
+    /// ```
+    fn gen_csv_field(&mut self) -> ~str {
+        static GEN_CSV_FIELD_CHARSET: &'static [u8] = bytes!("abcXYZ019 ,\"\r\n");
+        let len = self.gen_integer_range(0u, 16);
+        let mut field = str::with_capacity(len);
+        let mut needs_quotes = false;
+        for _ in range(0, len) {
+            let c = self.choose(GEN_CSV_FIELD_CHARSET) as char;
+            match c {
+                ',' | '"' | '\r' | '\n' => needs_quotes = true,
+                _ => ()
+            }
+            field.push_char(c);
+        }
+        if needs_quotes {
+            format!("\"{}\"", field.replace("\"", "\"\""))
+        } else {
+            field
+        }
+    }
+
+    /// Return a random but plausible HTTP User-Agent string, for testing
+    /// HTTP clients and servers.
+    ///
+    /// The string is one of a few templates for Firefox, Chrome and Safari,
+    /// filled in with a platform and a recent-looking version number, such
+    /// as "Mozilla/5.0 (X11; Linux x86_64; rv:115.0) Gecko/20100101
+    /// Firefox/115.0". It is syntactically valid as RFC 7231 describes, but
+    /// not weighted by real-world browser share.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::rand;
+    /// use std::rand::Rng;
+    ///
+    /// fn main() {
+    ///     let mut rng = rand::rng();
+    ///     println(rng.gen_user_agent());
+    /// }
+    /// ```
+    fn gen_user_agent(&mut self) -> ~str {
+        static PLATFORMS: &'static [&'static str] = &[
+            "Windows NT 10.0; Win64; x64",
+            "Windows NT 6.1; Win64; x64",
+            "Macintosh; Intel Mac OS X 10_15_7",
+            "X11; Linux x86_64",
+            "X11; Ubuntu; Linux x86_64",
+        ];
+        match self.gen_integer_range(0u, 3) {
+            0 => {
+                let version = self.gen_integer_range(90u, 130);
+                format!("Mozilla/5.0 ({}; rv:{}.0) Gecko/20100101 Firefox/{}.0",
+                        self.choose(PLATFORMS), version, version)
+            }
+            1 => {
+                format!("Mozilla/5.0 ({}) AppleWebKit/537.36 (KHTML, like Gecko) \
+                         Chrome/{}.0.{}.{} Safari/537.36",
+                        self.choose(PLATFORMS), self.gen_integer_range(100u, 130),
+                        self.gen_integer_range(4000u, 7000), self.gen_integer_range(0u, 200))
+            }
+            _ => {
+                format!("Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) \
+                         AppleWebKit/605.1.15 (KHTML, like Gecko) Version/{}.{} \
+                         Safari/605.1.15",
+                        self.gen_integer_range(14u, 18), self.gen_integer_range(0u, 6))
+            }
+        }
+    }
+
+    /// Choose an item randomly, failing if `values` is empty.
+    fn choose<T: Clone>(&mut self, values: &[T]) -> T {
+        self.choose_option(values).expect("Rng.choose: `values` is empty").clone()
+    }
+
+    /// Choose `Some(&item)` randomly, returning `None` if values is
+    /// empty.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::rand;
+    ///
+    /// fn main() {
+    ///     println!("{:?}", rand::task_rng().choose_option([1,2,4,8,16,32]));
+    ///     println!("{:?}", rand::task_rng().choose_option([]));
+    /// }
+    /// ```
+    fn choose_option<'a, T>(&mut self, values: &'a [T]) -> Option<&'a T> {
+        if values.is_empty() {
+            None
+        } else {
+            Some(&values[self.gen_integer_range(0u, values.len())])
+        }
+    }
+
+    /// Remove a randomly chosen element from `values` and return it, or
+    /// return `None` if `values` is empty. This draws without replacement.
+    ///
+    /// The last element is moved into the removed element's place, so this
+    /// takes constant time but does not preserve the order of the rest.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::rand;
+    /// use std::rand::Rng;
+    ///
+    /// fn main() {
+    ///     let mut rng = rand::rng();
+    ///     let mut raffle = ~[1, 2, 3, 4, 5];
+    ///     while !raffle.is_empty() {
+    ///         println!("ticket {:?} wins", rng.choose_remove(&mut raffle));
+    ///     }
+    /// }
+    /// ```
+    fn choose_remove<T>(&mut self, values: &mut ~[T]) -> Option<T> {
+        if values.is_empty() {
+            None
+        } else {
+            Some(values.swap_remove(self.gen_integer_range(0u, values.len())))
+        }
+    }
+
+    /// Choose an item respecting the relative weights, failing if the sum of
+    /// the weights is 0
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::rand;
+    /// use std::rand::Rng;
+    ///
+    /// fn main() {
+    ///     let mut rng = rand::rng();
+    ///     let x = [rand::Weighted {weight: 4, item: 'a'},
+    ///              rand::Weighted {weight: 2, item: 'b'},
+    ///              rand::Weighted {weight: 2, item: 'c'}];
+    ///     println!("{}", rng.choose_weighted(x));
+    /// }
+    /// ```
+    fn choose_weighted<T:Clone>(&mut self, v: &[Weighted<T>]) -> T {
+        self.choose_weighted_option(v).expect("Rng.choose_weighted: total weight is 0")
+    }
+
+    /// Choose Some(item) respecting the relative weights, returning none if
+    /// the sum of the weights is 0
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::rand;
+    /// use std::rand::Rng;
+    ///
+    /// fn main() {
+    ///     let mut rng = rand::rng();
+    ///     let x = [rand::Weighted {weight: 4, item: 'a'},
+    ///              rand::Weighted {weight: 2, item: 'b'},
+    ///              rand::Weighted {weight: 2, item: 'c'}];
+    ///     println!("{:?}", rng.choose_weighted_option(x));
+    /// }
+    /// ```
+    fn choose_weighted_option<T:Clone>(&mut self, v: &[Weighted<T>])
+                                       -> Option<T> {
+        self.choose_weighted_ref(v).map_move(|item| item.clone())
+    }
+
+    /// Choose Some(&item) respecting the relative weights, returning none if
+    /// the sum of the weights is 0. Unlike `choose_weighted_option` this
+    /// borrows the chosen item from `v` rather than cloning it.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::rand;
+    /// use std::rand::Rng;
+    ///
+    /// fn main() {
+    ///     let mut rng = rand::rng();
+    ///     let x = [rand::Weighted {weight: 4, item: ~[1, 2, 3]},
+    ///              rand::Weighted {weight: 2, item: ~[4, 5]},
+    ///              rand::Weighted {weight: 2, item: ~[6]}];
+    ///     println!("{:?}", rng.choose_weighted_ref(x));
+    /// }
+    /// ```
+    fn choose_weighted_ref<'a, T>(&mut self, v: &'a [Weighted<T>])
+                                  -> Option<&'a T> {
+        let mut total = 0u;
+        for item in v.iter() {
+            total += item.weight;
+        }
+        if total == 0u {
+            return None;
+        }
+        let chosen = self.gen_integer_range(0u, total);
+        let mut so_far = 0u;
+        for item in v.iter() {
+            so_far += item.weight;
+            if so_far > chosen {
+                return Some(&item.item);
+            }
+        }
+        unreachable!();
+    }
+
+    /// Choose an item respecting the relative fractional weights, failing
+    /// if the sum of the weights is not positive or any weight is negative
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::rand;
+    /// use std::rand::Rng;
+    ///
+    /// fn main() {
+    ///     let mut rng = rand::rng();
+    ///     let x = [rand::WeightedFloat {weight: 0.5, item: 'a'},
+    ///              rand::WeightedFloat {weight: 0.3, item: 'b'},
+    ///              rand::WeightedFloat {weight: 0.2, item: 'c'}];
+    ///     println!("{}", rng.choose_weighted_float(x));
+    /// }
+    /// ```
+    fn choose_weighted_float<T:Clone>(&mut self, v: &[WeightedFloat<T>]) -> T {
+        self.choose_weighted_float_option(v)
+            .expect("Rng.choose_weighted_float: total weight is not positive")
+    }
+
+    /// Choose Some(item) respecting the relative fractional weights,
+    /// returning none if the sum of the weights is not positive. Fails if
+    /// any weight is negative.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::rand;
+    /// use std::rand::Rng;
+    ///
+    /// fn main() {
+    ///     let mut rng = rand::rng();
+    ///     let x = [rand::WeightedFloat {weight: 0.5, item: 'a'},
+    ///              rand::WeightedFloat {weight: 0.3, item: 'b'},
+    ///              rand::WeightedFloat {weight: 0.2, item: 'c'}];
+    ///     println!("{:?}", rng.choose_weighted_float_option(x));
+    /// }
+    /// ```
+    fn choose_weighted_float_option<T:Clone>(&mut self, v: &[WeightedFloat<T>])
+                                             -> Option<T> {
+        let mut total = 0.0;
+        for item in v.iter() {
+            assert!(item.weight >= 0.0,
+                    "Rng.choose_weighted_float_option called with a negative weight");
+            total += item.weight;
+        }
+        if !(total > 0.0) {
+            return None;
+        }
+        let chosen = self.gen::<f64>() * total;
+        // The sums here repeat the ones above, so the last is `total`
+        // exactly and some item is always chosen.
+        let mut so_far = 0.0;
+        for item in v.iter() {
+            so_far += item.weight;
+            if so_far > chosen {
+                return Some(item.item.clone());
+            }
+        }
+        unreachable!();
+    }
+
+    /// Draw a value from an empirical distribution by inverse transform
+    /// sampling.
+    ///
+    /// `cdf[i]` is the probability of drawing a value no greater than
+    /// `sorted_values[i]`, so each value is returned with probability `cdf[i]
+    /// - cdf[i - 1]`. The result is always one of `sorted_values`; no
+    /// interpolation is done between them. Fails if the slices are empty or
+    /// of different lengths, if `cdf` decreases anywhere or if its last
+    /// entry is not within 1e-6 of 1.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::rand;
+    /// use std::rand::Rng;
+    ///
+    /// fn main() {
+    ///     let mut rng = rand::rng();
+    ///     let latencies = [10.0, 20.0, 50.0, 200.0];
+    ///     let cdf = [0.5, 0.9, 0.99, 1.0];
+    ///     println!("{}ms", rng.sample_empirical(latencies, cdf));
+    /// }
+    /// ```
+    fn sample_empirical(&mut self, sorted_values: &[f64], cdf: &[f64]) -> f64 {
+        assert!(!cdf.is_empty() && sorted_values.len() == cdf.len(),
+                "RNG.sample_empirical called with mismatched or empty tables");
+        assert!(range(1, cdf.len()).all(|i| cdf[i - 1] <= cdf[i]),
+                "RNG.sample_empirical called with a decreasing `cdf`");
+        assert!((cdf[cdf.len() - 1] - 1.0).abs() < 1e-6,
+                "RNG.sample_empirical called with a `cdf` that does not end at 1");
+
+        // Find the first entry whose cumulative probability exceeds `u`. The
+        // last entry is taken if `cdf` ends fractionally short of 1.
+        let u = self.gen::<f64>();
+        let (mut lo, mut hi) = (0u, cdf.len() - 1);
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if cdf[mid] > u {
+                hi = mid;
+            } else {
+                lo = mid + 1;
+            }
+        }
+        sorted_values[lo]
+    }
+
+    /// Return a vec containing copies of the items, in order, where
+    /// the weight of the item determines how many copies there are
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::rand;
+    /// use std::rand::Rng;
+    ///
+    /// fn main() {
+    ///     let mut rng = rand::rng();
+    ///     let x = [rand::Weighted {weight: 4, item: 'a'},
+    ///              rand::Weighted {weight: 2, item: 'b'},
+    ///              rand::Weighted {weight: 2, item: 'c'}];
+    ///     println!("{}", rng.weighted_vec(x));
+    /// }
+    /// ```
+    fn weighted_vec<T:Clone>(&mut self, v: &[Weighted<T>]) -> ~[T] {
+        let mut r = ~[];
+        for item in v.iter() {
+            for _ in range(0u, item.weight) {
+                r.push(item.item.clone());
+            }
+        }
+        r
+    }
+
+    /// Return a `rows` by `cols` matrix, as a vector of rows, with each entry
+    /// drawn independently from `dist`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::rand;
+    /// use std::rand::Rng;
+    /// use std::rand::distributions::Normal;
+    ///
+    /// fn main() {
+    ///     let mut rng = rand::rng();
+    ///     let m = rng.gen_matrix(3, 4, &Normal::new(0.0, 1.0));
+    ///     println!("{:?}", m);
+    /// }
+    /// ```
+    fn gen_matrix<D: distributions::Distribution<f64>>(&mut self, rows: uint, cols: uint,
+                                                       dist: &D) -> ~[~[f64]] {
+        vec::from_fn(rows, |_| vec::from_fn(cols, |_| dist.sample(self)))
+    }
+
+    /// Return a random `n` by `n` orthogonal matrix, as a vector of rows.
+    ///
+    /// The rows of a matrix of standard normal values are orthonormalised
+    /// with the (modified) Gram-Schmidt process, so the rows, and hence the
+    /// columns, of the result are unit vectors orthogonal to each other.
+    fn gen_orthogonal(&mut self, n: uint) -> ~[~[f64]] {
+        let mut m = self.gen_matrix(n, n, &distributions::Normal::new(0.0, 1.0));
+        for i in range(0, n) {
+            for j in range(0, i) {
+                let mut dot = 0.0;
+                for k in range(0, n) {
+                    dot += m[i][k] * m[j][k];
+                }
+                for k in range(0, n) {
+                    let x = m[j][k];
+                    m[i][k] -= dot * x;
+                }
+            }
+            let norm = m[i].iter().fold(0.0, |sum, &x| sum + x * x).sqrt();
+            for x in m[i].mut_iter() {
+                *x /= norm;
+            }
+        }
+        m
+    }
+
+    /// Return the nonzero entries of a random sparse vector of length `len`
+    /// as `(index, value)` pairs, in increasing order of index.
+    ///
+    /// Each index is included independently with probability `density`, so
+    /// there are about `density * len` entries, with distinct indices in
+    /// `[0, len)`. Values are drawn uniformly from `[0, 1)`. Fails if
+    /// `density` is outside `[0, 1]`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::rand;
+    /// use std::rand::Rng;
+    ///
+    /// fn main() {
+    ///     let mut rng = rand::rng();
+    ///     for &(i, x) in rng.gen_sparse(1000, 0.01).iter() {
+    ///         println!("v[{}] = {}", i, x);
+    ///     }
+    /// }
+    /// ```
+    fn gen_sparse(&mut self, len: uint, density: f64) -> ~[(uint, f64)] {
+        assert!(density >= 0.0 && density <= 1.0,
+                "RNG.gen_sparse called with `density` outside [0, 1]");
+        let mut entries = ~[];
+        for i in range(0, len) {
+            if self.gen::<f64>() < density {
+                entries.push((i, self.gen::<f64>()));
+            }
+        }
+        entries
+    }
+
+    /// Return a random rooted tree with `n` nodes, as a vector where element
+    /// `i` is the parent of node `i`.
+    ///
+    /// Node 0 is the root and is its own parent. Every other node's parent
+    /// is chosen uniformly from the nodes before it, so parents always have
+    /// smaller indices and the result can never contain a cycle.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::rand;
+    /// use std::rand::Rng;
+    ///
+    /// fn main() {
+    ///     let mut rng = rand::rng();
+    ///     let parents = rng.gen_random_tree(10);
+    ///     for (node, parent) in parents.iter().enumerate().skip(1) {
+    ///         println!("{} -> {}", *parent, node);
+    ///     }
+    /// }
+    /// ```
+    fn gen_random_tree(&mut self, n: uint) -> ~[uint] {
+        vec::from_fn(n, |i| if i == 0 { 0 } else { self.gen_integer_range(0, i) })
+    }
+
+    /// Return a random walk of `steps` steps starting at `start`: a vector
+    /// of `steps + 1` values whose first element is `start` and where each
+    /// step adds a value drawn uniformly from `[-step_range, step_range]`.
+    /// Fails if `step_range` is negative.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::rand;
+    /// use std::rand::Rng;
+    ///
+    /// fn main() {
+    ///     let mut rng = rand::rng();
+    ///     let prices = rng.gen_walk(100, 30, 5);
+    ///     println!("{:?}", prices);
+    /// }
+    /// ```
+    fn gen_walk(&mut self, start: i64, steps: uint, step_range: i64) -> ~[i64] {
+        assert!(step_range >= 0, "RNG.gen_walk called with a negative `step_range`");
+        let mut walk = vec::with_capacity(steps + 1);
+        let mut x = start;
+        walk.push(x);
+        for _ in range(0, steps) {
+            x += self.gen_integer_range(-step_range, step_range + 1);
+            walk.push(x);
+        }
+        walk
+    }
+
+    /// Return a random perfect maze of `width` by `height` cells, carved
+    /// by a randomized depth-first search. Every cell is reachable from
+    /// every other by exactly one path.
+    ///
+    /// The result is a grid of `2 * height + 1` rows of `2 * width + 1`
+    /// squares where `true` is a wall: cell `(x, y)` is the open square at
+    /// row `2 * y + 1`, column `2 * x + 1`, and the squares between cells
+    /// are open where a passage joins them. The outer border is all wall.
+    /// Fails if `width` or `height` is 0.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::rand;
+    /// use std::rand::Rng;
+    ///
+    /// fn main() {
+    ///     let mut rng = rand::rng();
+    ///     for row in rng.gen_maze(8, 4).iter() {
+    ///         let line = row.iter().map(|&wall| if wall { '#' } else { ' ' });
+    ///         println(line.collect::<~str>());
+    ///     }
+    /// }
+    /// ```
+    fn gen_maze(&mut self, width: uint, height: uint) -> ~[~[bool]] {
+        assert!(width > 0 && height > 0, "RNG.gen_maze called with an empty maze");
+        let mut walls = vec::from_elem(2 * height + 1, vec::from_elem(2 * width + 1, true));
+        let mut visited = vec::from_elem(width * height, false);
+        let mut stack = ~[(0u, 0u)];
+        visited[0] = true;
+        walls[1][1] = false;
+
+        loop {
+            let (x, y) = match stack.last_opt() {
+                Some(&cell) => cell,
+                None => break
+            };
+            let mut unvisited = ~[];
+            if x > 0 && !visited[y * width + x - 1] { unvisited.push((x - 1, y)); }
+            if x + 1 < width && !visited[y * width + x + 1] { unvisited.push((x + 1, y)); }
+            if y > 0 && !visited[(y - 1) * width + x] { unvisited.push((x, y - 1)); }
+            if y + 1 < height && !visited[(y + 1) * width + x] { unvisited.push((x, y + 1)); }
+            if unvisited.is_empty() {
+                stack.pop();
+                continue;
+            }
+
+            let (nx, ny) = unvisited[self.gen_integer_range(0u, unvisited.len())];
+            visited[ny * width + nx] = true;
+            walls[2 * ny + 1][2 * nx + 1] = false;
+            // The square between the two cells.
+            walls[y + ny + 1][x + nx + 1] = false;
+            stack.push((nx, ny));
+        }
+        walls
+    }
+
+    /// Shuffle a vec
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::rand;
+    ///
+    /// fn main() {
+    ///     println!("{:?}", rand::task_rng().shuffle(~[1,2,3]));
+    /// }
+    /// ```
+    fn shuffle<T>(&mut self, values: ~[T]) -> ~[T] {
+        let mut v = values;
+        self.shuffle_mut(v);
+        v
+    }
+
+    /// Shuffle a mutable vector in place.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::rand;
+    ///
+    /// fn main() {
+    ///    let rng = rand::task_rng();
+    ///    let mut y = [1,2,3];
+    ///    rng.shuffle_mut(y);
+    ///    println!("{:?}", y);
+    ///    rng.shuffle_mut(y);
+    ///    println!("{:?}", y);
+    /// }
+    /// ```
+    fn shuffle_mut<T>(&mut self, values: &mut [T]) {
+        let mut i = values.len();
+        while i >= 2u {
+            // invariant: elements with index >= i have been locked in place.
+            i -= 1u;
+            // lock element i in place.
+            values.swap(i, self.gen_integer_range(0u, i + 1u));
+        }
+    }
+
+    /// Return the permutation that `shuffle_mut` would apply to a slice of
+    /// length `len`, drawing the same numbers from the generator. Element
+    /// `i` of the slice shuffled in place is element `indices[i]` of the
+    /// original.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::rand;
+    /// use std::rand::Rng;
+    ///
+    /// fn main() {
+    ///     let mut rng = rand::rng();
+    ///     let names = ["a", "b", "c"];
+    ///     for &i in rng.shuffle_indices(names.len()).iter() {
+    ///         println(names[i]);
+    ///     }
+    /// }
+    /// ```
+    fn shuffle_indices(&mut self, len: uint) -> ~[uint] {
+        let mut indices = vec::from_fn(len, |i| i);
+        self.shuffle_mut(indices);
+        indices
+    }
+
+    /// Return an iterator that draws the elements of `values` in a random
+    /// order, yielding each exactly once. The draws are made lazily, so
+    /// `.take(k)` makes `k` draws without replacement.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::rand;
+    ///
+    /// fn main() {
+    ///    let mut rng = rand::rng();
+    ///    let balls = range(1, 50).collect::<~[int]>();
+    ///    let draw = rng.draw_iter(balls).take(6).collect::<~[int]>();
+    ///    println!("{:?}", draw);
+    /// }
+    /// ```
+    fn draw_iter<'a, T: Clone>(&'a mut self, values: &'a [T]) -> DrawIter<'a, Self, T> {
+        DrawIter {
+            rng: self,
+            values: values,
+            remaining: vec::from_fn(values.len(), |i| i)
+        }
+    }
+
+    /// Choose `k` distinct elements of `values` in a random order, without
+    /// replacement. If `values` has fewer than `k` elements, all of them are
+    /// returned, shuffled.
+    ///
+    /// This runs a partial Fisher-Yates shuffle, recording only the
+    /// positions that have been swapped, so it takes `O(k)` time and space
+    /// however long `values` is.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::rand;
+    /// use std::rand::Rng;
+    ///
+    /// fn main() {
+    ///     let mut rng = rand::rng();
+    ///     let deck = range(0, 52).collect::<~[int]>();
+    ///     println!("{:?}", rng.choose_multiple(deck, 5));
+    /// }
+    /// ```
+    fn choose_multiple<T: Clone>(&mut self, values: &[T], k: uint) -> ~[T] {
+        let n = values.len();
+        let k = cmp::min(k, n);
+        // swapped.find(&j) is the index now sitting at position j, for
+        // every position j that has been disturbed by a swap.
+        let mut swapped: HashMap<uint, uint> = HashMap::with_capacity(2 * k);
+        let mut result = vec::with_capacity(k);
+        for i in range(0u, k) {
+            let j = self.gen_integer_range(i, n);
+            let at_i = swapped.find_copy(&i).unwrap_or(i);
+            let at_j = swapped.find_copy(&j).unwrap_or(j);
+            swapped.insert(j, at_i);
+            result.push(values[at_j].clone());
+        }
+        result
+    }
+
+    /// Randomly sample up to `n` elements from an iterator.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::rand;
+    ///
+    /// fn main() {
+    ///    let rng = rand::task_rng();
+    ///    let sample = rng.sample(range(1, 100), 5);
+    ///    println!("{:?}", sample);
+    /// }
+    /// ```
+    fn sample<A, T: Iterator<A>>(&mut self, iter: T, n: uint) -> ~[A] {
+        let mut reservoir : ~[A] = vec::with_capacity(n);
+        for (i, elem) in iter.enumerate() {
+            if i < n {
+                reservoir.push(elem);
+                continue
+            }
+
+            let k = self.gen_integer_range(0, i + 1);
+            if k < reservoir.len() {
+                reservoir[k] = elem
+            }
+        }
+        reservoir
+    }
+}
+
+/// A random number generator that can be explicitly seeded to produce
+/// the same stream of randomness multiple times.
+pub trait SeedableRng<Seed>: Rng {
+    /// Reseed the generator in place, so that it produces the same stream
+    /// as a generator newly created with `from_seed(seed)`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::rand::{Rng, SeedableRng, XorShiftRng};
+    ///
+    /// fn main() {
+    ///     let mut rng: XorShiftRng = SeedableRng::from_seed([1, 2, 3, 4]);
+    ///     let first = rng.gen::<f64>();
+    ///     rng.reseed([1, 2, 3, 4]);
+    ///     assert_eq!(rng.gen::<f64>(), first);
+    /// }
+    /// ```
+    fn reseed(&mut self, seed: Seed);
+
+    /// Create a new generator with the given seed.
+    fn from_seed(seed: Seed) -> Self;
+}
+
+/// Create a random number generator with a default algorithm and seed.
+///
+/// It returns the cryptographically-safest `Rng` algorithm currently
+/// available in Rust. If you require a specifically seeded `Rng` for
+/// consistency over time you should pick one algorithm and create the
+/// `Rng` yourself, or use `PortableRng` which also gives the same results
+/// on every platform.
+pub fn rng() -> IsaacRng {
+    IsaacRng::new()
+}
+
+/// Create a weak random number generator with a default algorithm and seed.
+///
+/// It returns the fastest `Rng` algorithm currently available in Rust without
+/// consideration for cryptography or security. If you require a specifically
+/// seeded `Rng` for consistency over time you should pick one algorithm and
+/// create the `Rng` yourself.
+pub fn weak_rng() -> XorShiftRng {
     XorShiftRng::new()
 }
 
@@ -734,6 +1872,15 @@ impl IsaacRng {
     }
 }
 
+// Fixed length vectors don't implement Clone, so this can't be derived.
+impl Clone for IsaacRng {
+    /// Returns a generator with the same state as `self`, which will
+    /// produce the same stream of numbers.
+    fn clone(&self) -> IsaacRng {
+        *self
+    }
+}
+
 impl Rng for IsaacRng {
     #[inline]
     fn next(&mut self) -> u32 {
@@ -746,12 +1893,34 @@ impl Rng for IsaacRng {
     }
 }
 
+impl<'self> SeedableRng<&'self [u32]> for IsaacRng {
+    /// Reseed with up to `RAND_SIZE` words; missing words are taken as 0
+    /// and any more are silently ignored.
+    fn reseed(&mut self, seed: &'self [u32]) {
+        for (i, word) in self.rsl.mut_iter().enumerate() {
+            *word = if i < seed.len() { seed[i] } else { 0 };
+        }
+        self.cnt = 0;
+        self.a = 0;
+        self.b = 0;
+        self.c = 0;
+        self.init(true);
+    }
+
+    fn from_seed(seed: &'self [u32]) -> IsaacRng {
+        let mut rng = IsaacRng::new_unseeded();
+        rng.reseed(seed);
+        rng
+    }
+}
+
 /// An [Xorshift random number
 /// generator](http://en.wikipedia.org/wiki/Xorshift).
 ///
 /// The Xorshift algorithm is not suitable for cryptographic purposes
 /// but is very fast. If you do not know for sure that it fits your
 /// requirements, use a more secure one such as `IsaacRng`.
+#[deriving(Clone)]
 pub struct XorShiftRng {
     priv x: u32,
     priv y: u32,
@@ -807,6 +1976,331 @@ impl XorShiftRng {
             w: w,
         }
     }
+
+    /**
+     * Advance the generator by exactly 2^64 steps, as if `next` had been
+     * called 2^64 times, at the cost of 128 calls. The generator's period
+     * is 2^128 - 1, so generators cloned from one state and then jumped 0,
+     * 1, 2, ... times produce non-overlapping streams of 2^64 values each,
+     * for up to 2^64 of them.
+     */
+    pub fn jump(&mut self) {
+        // The coefficients of x^(2^64) modulo the characteristic polynomial
+        // of the state transition, lowest first.
+        static JUMP: [u32, .. 4] = [0x35aac71c, 0x821e5343, 0xf52e65c4, 0xd8cd644e];
+
+        let (mut x, mut y, mut z, mut w) = (0u32, 0u32, 0u32, 0u32);
+        for &word in JUMP.iter() {
+            for bit in range(0u32, 32) {
+                if word & (1 << bit) != 0 {
+                    x ^= self.x;
+                    y ^= self.y;
+                    z ^= self.z;
+                    w ^= self.w;
+                }
+                self.next();
+            }
+        }
+        self.x = x;
+        self.y = y;
+        self.z = z;
+        self.w = w;
+    }
+}
+
+impl SeedableRng<[u32, .. 4]> for XorShiftRng {
+    /// Reseed the generator. Fails if the seed is all zeros, which would
+    /// make the generator produce nothing but zeros.
+    fn reseed(&mut self, seed: [u32, .. 4]) {
+        assert!(!seed.iter().all(|&x| x == 0),
+                "XorShiftRng.reseed called with an all zero seed.");
+        self.x = seed[0];
+        self.y = seed[1];
+        self.z = seed[2];
+        self.w = seed[3];
+    }
+
+    /// Create a new generator. Fails if the seed is all zeros.
+    fn from_seed(seed: [u32, .. 4]) -> XorShiftRng {
+        let mut rng = XorShiftRng::new_seeded(1, 1, 1, 1);
+        rng.reseed(seed);
+        rng
+    }
+}
+
+/// A random number generator whose output for a given seed is the same on
+/// every platform.
+///
+/// `rng()` and `task_rng()` return whichever algorithm is currently
+/// considered best, so the sequence they produce from a seed is not
+/// guaranteed to stay the same. `PortableRng` always uses the 32-bit [ISAAC
+/// algorithm](http://en.wikipedia.org/wiki/ISAAC_%28cipher%29) and reads its
+/// seed as little-endian words, so a simulation seeded with the same bytes
+/// reproduces exactly on 32- and 64-bit, little- and big-endian machines.
+///
+/// Note that generating `int` or `uint` values directly is still not
+/// portable: they consume one `u32` on 32-bit targets but a `u64` on 64-bit
+/// ones. Use fixed width types such as `u32` or `i64` instead. The default
+/// `Rng` methods that take `uint` arguments, such as `gen_integer_range`,
+/// `choose` and `shuffle`, always draw 64 bits and are portable.
+#[deriving(Clone)]
+pub struct PortableRng {
+    priv isaac: IsaacRng,
+    priv seed: ~[u8]
+}
+
+impl PortableRng {
+    /// Create a portable random number generator with a random seed.
+    pub fn new() -> PortableRng {
+        PortableRng::new_seeded(seed())
+    }
+
+    /// Create a portable random number generator with a seed. At most 1024
+    /// bytes of the seed are used, any more are silently ignored.
+    pub fn new_seeded(seed: &[u8]) -> PortableRng {
+        let mut isaac = IsaacRng {
+            cnt: 0,
+            rsl: [0, .. RAND_SIZE],
+            mem: [0, .. RAND_SIZE],
+            a: 0, b: 0, c: 0
+        };
+
+        // Assemble the seed words explicitly rather than copying the bytes,
+        // so the result does not depend on the byte order of the target.
+        let seed = seed.slice_to(cmp::min(seed.len(), RAND_SIZE as uint * 4));
+        for (i, &byte) in seed.iter().enumerate() {
+            isaac.rsl[i / 4] |= (byte as u32) << (8 * (i % 4));
+        }
+        isaac.init(true);
+        PortableRng { isaac: isaac, seed: seed.to_owned() }
+    }
+
+    /// Create an independent generator identified by `label`.
+    ///
+    /// The child is seeded by hashing this generator's seed together with
+    /// the label, so the same seed and label always give the same stream,
+    /// no matter how much has already been drawn from either generator,
+    /// while different labels give unrelated streams. Children can in turn
+    /// create their own substreams.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::rand::{Rng, PortableRng};
+    ///
+    /// fn main() {
+    ///     let master = PortableRng::new_seeded(bytes!("simulation 42"));
+    ///     let mut weather = master.substream("weather");
+    ///     let mut traffic = master.substream("traffic");
+    ///     println!("{} {}", weather.gen::<f64>(), traffic.gen::<f64>());
+    /// }
+    /// ```
+    pub fn substream(&self, label: &str) -> PortableRng {
+        let mut seed = ~[];
+        for i in range(0u8, 4) {
+            let mut state = hash::default_state();
+            state.input(&[i]);
+            state.input(self.seed);
+            state.input(label.as_bytes());
+            seed.push_all(state.result_bytes());
+        }
+        PortableRng::new_seeded(seed)
+    }
+}
+
+impl Rng for PortableRng {
+    #[inline]
+    fn next(&mut self) -> u32 {
+        self.isaac.next()
+    }
+}
+
+impl<'self> SeedableRng<&'self [u8]> for PortableRng {
+    fn reseed(&mut self, seed: &'self [u8]) {
+        *self = PortableRng::new_seeded(seed);
+    }
+
+    fn from_seed(seed: &'self [u8]) -> PortableRng {
+        PortableRng::new_seeded(seed)
+    }
+}
+
+/// A wrapper that records every value drawn from another random number
+/// generator, so that a run can be reproduced exactly with a `PlaybackRng`,
+/// e.g. when debugging a flaky randomized test.
+///
+/// # Example
+///
+/// ```rust
+/// use std::rand;
+/// use std::rand::{Rng, RecordingRng, PlaybackRng};
+///
+/// fn main() {
+///     let mut recording = RecordingRng::new(rand::rng());
+///     let x = recording.gen_vec::<f64>(3);
+///     let mut playback = PlaybackRng::new(recording.recorded().to_owned());
+///     assert_eq!(playback.gen_vec::<f64>(3), x);
+/// }
+/// ```
+pub struct RecordingRng<R> {
+    priv inner: R,
+    priv recorded: ~[u32]
+}
+
+impl<R: Rng> RecordingRng<R> {
+    /// Create a recording wrapper around `inner`.
+    pub fn new(inner: R) -> RecordingRng<R> {
+        RecordingRng { inner: inner, recorded: ~[] }
+    }
+
+    /// The values drawn so far, oldest first.
+    pub fn recorded<'a>(&'a self) -> &'a [u32] {
+        self.recorded.as_slice()
+    }
+}
+
+impl<R: Rng> Rng for RecordingRng<R> {
+    #[inline]
+    fn next(&mut self) -> u32 {
+        let x = self.inner.next();
+        self.recorded.push(x);
+        x
+    }
+}
+
+/// A random number generator that replays a fixed stream of values, such as
+/// one captured by a `RecordingRng`. Fails if asked for more values than it
+/// was given.
+pub struct PlaybackRng {
+    priv values: ~[u32],
+    priv pos: uint
+}
+
+impl PlaybackRng {
+    /// Create a generator that returns `values` in order.
+    pub fn new(values: ~[u32]) -> PlaybackRng {
+        PlaybackRng { values: values, pos: 0 }
+    }
+}
+
+impl Rng for PlaybackRng {
+    #[inline]
+    fn next(&mut self) -> u32 {
+        assert!(self.pos < self.values.len(), "PlaybackRng: recorded values exhausted");
+        let x = self.values[self.pos];
+        self.pos += 1;
+        x
+    }
+}
+
+/// A weighted random sample of at most `k` items from a stream of unknown
+/// length, maintained incrementally with the A-Res algorithm of Efraimidis
+/// and Spirakis: each item is given the key `u^(1/weight)` for `u` uniform
+/// on `[0, 1)`, and the `k` items with the largest keys are kept. Heavier
+/// items are correspondingly more likely to end up in the sample, and only
+/// `k` items are ever held in memory.
+///
+/// # Example
+///
+/// ```rust
+/// use std::rand;
+/// use std::rand::WeightedReservoir;
+///
+/// fn main() {
+///     let mut rng = rand::rng();
+///     let mut sample = WeightedReservoir::new(2);
+///     for &(name, population) in [("a", 100.0), ("b", 5000.0), ("c", 20.0)].iter() {
+///         sample.offer(&mut rng, name, population);
+///     }
+///     println!("{:?}", sample.into_vec());
+/// }
+/// ```
+pub struct WeightedReservoir<T> {
+    priv k: uint,
+    // `(ln key, item)`: the log is monotonic in the key but does not
+    // underflow for small weights.
+    priv entries: ~[(f64, T)]
+}
+
+impl<T> WeightedReservoir<T> {
+    /// Create an empty reservoir that keeps at most `k` items.
+    pub fn new(k: uint) -> WeightedReservoir<T> {
+        WeightedReservoir { k: k, entries: vec::with_capacity(k) }
+    }
+
+    /// Offer the next item from the stream with the given weight. Fails
+    /// unless `weight` is positive and finite.
+    pub fn offer<R: Rng>(&mut self, rng: &mut R, item: T, weight: f64) {
+        assert!(weight > 0.0 && weight.is_finite(),
+                "WeightedReservoir::offer called with a non-positive weight");
+        let key = rng.gen::<f64>().ln() / weight;
+        if self.entries.len() < self.k {
+            self.entries.push((key, item));
+            return;
+        }
+        let mut min = None;
+        for (i, &(other, _)) in self.entries.iter().enumerate() {
+            match min {
+                Some((_, m)) if m <= other => (),
+                _ => min = Some((i, other))
+            }
+        }
+        match min {
+            Some((i, m)) if key > m => self.entries[i] = (key, item),
+            _ => ()
+        }
+    }
+
+    /// The number of items currently in the reservoir.
+    pub fn len(&self) -> uint {
+        self.entries.len()
+    }
+
+    /// Consume the reservoir, returning the sampled items in no particular
+    /// order.
+    pub fn into_vec(self) -> ~[T] {
+        self.entries.move_iter().map(|(_, item)| item).collect()
+    }
+}
+
+/// An endless iterator of random values of type `T`, created by
+/// `Rng::gen_iter`.
+pub struct RandIterator<'self, R, T> {
+    priv rng: &'self mut R
+}
+
+impl<'self, R: Rng, T: Rand> Iterator<T> for RandIterator<'self, R, T> {
+    fn next(&mut self) -> Option<T> {
+        Some(self.rng.gen())
+    }
+
+    fn size_hint(&self) -> (uint, Option<uint>) {
+        (uint::max_value, None)
+    }
+}
+
+/// An iterator over the elements of a slice in random order, created by
+/// `Rng::draw_iter`.
+pub struct DrawIter<'self, R, T> {
+    priv rng: &'self mut R,
+    priv values: &'self [T],
+    // Indices into `values` not yet drawn
+    priv remaining: ~[uint]
+}
+
+impl<'self, R: Rng, T: Clone> Iterator<T> for DrawIter<'self, R, T> {
+    fn next(&mut self) -> Option<T> {
+        let n = self.remaining.len();
+        if n == 0 {
+            return None;
+        }
+        let i = self.remaining.swap_remove(self.rng.gen_integer_range(0u, n));
+        Some(self.values[i].clone())
+    }
+
+    fn size_hint(&self) -> (uint, Option<uint>) {
+        (self.remaining.len(), Some(self.remaining.len()))
+    }
 }
 
 /// Create a new random seed.
@@ -863,9 +2357,17 @@ pub fn random<T: Rand>() -> T {
 
 #[cfg(test)]
 mod test {
-    use iter::{Iterator, range};
+    use borrow;
+    use hashmap::HashMap;
+    use i64;
+    use int;
+    use iter::{Iterator, range, range_step};
+    use num;
     use option::{Option, Some};
     use super::*;
+    use u64;
+    use uint;
+    use vec;
 
     #[test]
     fn test_rng_seeded() {
@@ -895,6 +2397,123 @@ mod test {
                      || r == 2935188040u32); // on x86
     }
 
+    #[test]
+    fn test_portable_rng_pinned() {
+        // These values must never change; simulations depend on them.
+        let mut r = PortableRng::new_seeded(bytes!("portable"));
+        assert_eq!(r.gen_vec::<u32>(4),
+                   ~[319574859, 1274532990, 1026383987, 1281351414]);
+
+        let mut r = PortableRng::new_seeded(bytes!("portable"));
+        let v: ~[uint] = range(0, 10).map(|_| r.gen_integer_range(0u, 10)).collect();
+        assert_eq!(v, ~[4, 6, 8, 2, 7, 2, 3, 8, 6, 8]);
+
+        // Matches plain ISAAC when the seed is read as little-endian words.
+        let seed = [2u8, 32u8, 4u8, 32u8, 51u8];
+        let mut r = PortableRng::new_seeded(seed);
+        assert_eq!(r.next(), 2935188040u32);
+    }
+
+    #[test]
+    fn test_portable_rng_substream() {
+        let master = PortableRng::new_seeded(bytes!("master"));
+        let a = master.substream("weather").gen_vec::<u32>(16);
+        assert_eq!(master.substream("weather").gen_vec::<u32>(16), a);
+        assert!(master.substream("traffic").gen_vec::<u32>(16) != a);
+        assert!(PortableRng::new_seeded(bytes!("other")).substream("weather")
+                .gen_vec::<u32>(16) != a);
+
+        // Drawing from the master does not affect its substreams.
+        let mut master = master;
+        master.gen_vec::<u32>(100);
+        assert_eq!(master.substream("weather").gen_vec::<u32>(16), a);
+
+        let nested = master.substream("weather").substream("rain").gen_vec::<u32>(16);
+        assert!(nested != a);
+        assert_eq!(master.substream("weather").substream("rain").gen_vec::<u32>(16),
+                   nested);
+    }
+
+    #[test]
+    fn test_weighted_reservoir() {
+        let mut r = rng();
+
+        // Fewer items than `k` are all kept.
+        let mut res = WeightedReservoir::new(5);
+        for i in range(0u, 3) {
+            res.offer(&mut r, i, 1.0);
+        }
+        let v = res.into_vec();
+        assert_eq!(v.len(), 3);
+        assert!(v.contains(&0) && v.contains(&1) && v.contains(&2));
+
+        // Item 0 has weight 100, items 1 to 99 weight 1, so with k = 1 item 0
+        // is picked with probability 100 / 199, and each other item with
+        // probability 1 / 199.
+        let mut heavy = 0;
+        let mut counts = [0u, ..100];
+        for _ in range(0, 2000) {
+            let mut res = WeightedReservoir::new(1);
+            for i in range(0u, 100) {
+                res.offer(&mut r, i, if i == 0 { 100.0 } else { 1.0 });
+            }
+            assert_eq!(res.len(), 1);
+            let v = res.into_vec();
+            counts[v[0]] += 1;
+            if v[0] == 0 { heavy += 1 }
+        }
+        assert!(heavy > 900 && heavy < 1100);
+        assert!(counts.iter().skip(1).all(|&c| c < 60));
+
+        // With k = 10 of 1000 items, the 10 items of weight 1000 dominate:
+        // on average about 8.3 of them are in the sample.
+        let mut heavy = 0;
+        for _ in range(0, 20) {
+            let mut res = WeightedReservoir::new(10);
+            for i in range(0u, 1000) {
+                res.offer(&mut r, i, if i % 100 == 0 { 1000.0 } else { 1.0 });
+            }
+            let v = res.into_vec();
+            assert_eq!(v.len(), 10);
+            heavy += v.iter().count(|&i| i % 100 == 0);
+        }
+        assert!(heavy > 140);
+    }
+
+    #[test]
+    #[should_fail]
+    fn test_weighted_reservoir_zero_weight() {
+        let mut r = rng();
+        let mut res = WeightedReservoir::new(1);
+        res.offer(&mut r, (), 0.0);
+    }
+
+    #[test]
+    fn test_recording_playback_rng() {
+        fn computation<R: Rng>(r: &mut R) -> (~[u8], f64, ~str, ~[int]) {
+            let v = r.gen_vec::<u8>(10);
+            let x = r.gen::<f64>();
+            let s = r.gen_ascii_str(20);
+            let shuffled = r.shuffle(~[1, 2, 3, 4, 5, 6, 7, 8]);
+            (v, x, s, shuffled)
+        }
+
+        let mut recording = RecordingRng::new(rng());
+        let expected = computation(&mut recording);
+        assert!(!recording.recorded().is_empty());
+
+        let mut playback = PlaybackRng::new(recording.recorded().to_owned());
+        assert_eq!(computation(&mut playback), expected);
+    }
+
+    #[test]
+    #[should_fail]
+    fn test_playback_rng_exhausted() {
+        let mut r = PlaybackRng::new(~[1, 2]);
+        r.gen::<u64>();
+        r.next();
+    }
+
     #[test]
     fn test_gen_integer_range() {
         let mut r = rng();
@@ -928,6 +2547,43 @@ mod test {
         r.gen_integer_range(5u, 2u);
     }
 
+    #[test]
+    fn test_gen_integer_range_inclusive() {
+        let mut r = rng();
+        let mut seen = [false, ..256];
+        for _ in range(0, 10000) {
+            seen[r.gen_integer_range_inclusive(0u8, 255) as uint] = true;
+        }
+        assert!(seen.iter().all(|&x| x));
+
+        let mut seen = [false, ..11];
+        for _ in range(0, 1000) {
+            let a = r.gen_integer_range_inclusive(-5i, 5);
+            assert!(a >= -5 && a <= 5);
+            seen[(a + 5) as uint] = true;
+        }
+        assert!(seen.iter().all(|&x| x));
+
+        for _ in range(0, 1000) {
+            let a = r.gen_integer_range_inclusive(-100i8, 100);
+            assert!(a >= -100 && a <= 100);
+            let b = r.gen_integer_range_inclusive(u64::max_value - 1, u64::max_value);
+            assert!(b >= u64::max_value - 1);
+            assert_eq!(r.gen_integer_range_inclusive(7i, 7), 7);
+            assert_eq!(r.gen_integer_range_inclusive(int::max_value, int::max_value),
+                       int::max_value);
+        }
+        r.gen_integer_range_inclusive(i64::min_value, i64::max_value);
+        r.gen_integer_range_inclusive(0u64, u64::max_value);
+    }
+
+    #[test]
+    #[should_fail]
+    fn test_gen_integer_range_inclusive_fail() {
+        let mut r = rng();
+        r.gen_integer_range_inclusive(5i, 4);
+    }
+
     #[test]
     fn test_gen_f64() {
         let mut r = rng();
@@ -937,10 +2593,164 @@ mod test {
     }
 
     #[test]
-    fn test_gen_weighted_bool() {
+    fn test_gen_weighted_bool() {
+        let mut r = rng();
+        assert_eq!(r.gen_weighted_bool(0u), true);
+        assert_eq!(r.gen_weighted_bool(1u), true);
+    }
+
+    #[test]
+    fn test_gen_bool() {
+        let mut r = rng();
+        let mut trues = 0;
+        for _ in range(0, 10000) {
+            assert!(!r.gen_bool(0.0));
+            assert!(r.gen_bool(1.0));
+            if r.gen_bool(0.3) { trues += 1 }
+        }
+        // 3000 expected, with a standard deviation of about 46.
+        assert!(trues > 2750 && trues < 3250);
+    }
+
+    #[test]
+    #[should_fail]
+    fn test_gen_bool_invalid() {
+        rng().gen_bool(1.5);
+    }
+
+    #[test]
+    fn test_gen_bools_packed() {
+        let mut r = rng();
+        let n = 100000;
+        for &p in [0.5, 0.1, 0.75, 0.3].iter() {
+            let mut out = [0u64, .. 1564];
+            r.gen_bools_packed(n, p, out);
+            let ones = out.iter().fold(0u, |a, &w| a + w.population_count() as uint);
+            // The standard deviation is at most 0.0016.
+            assert!(num::abs(ones as f64 / n as f64 - p) < 0.01);
+            // Only the first `n` bits may be set.
+            assert_eq!(out[1562] >> (n - 1562 * 64) as u64, 0);
+            assert_eq!(out[1563], 0);
+        }
+
+        let mut out = [!0u64, .. 3];
+        r.gen_bools_packed(100, 1.0, out);
+        assert_eq!(out.to_owned(), ~[!0, (1 << 36) - 1, 0]);
+        r.gen_bools_packed(100, 0.0, out);
+        assert_eq!(out.to_owned(), ~[0, 0, 0]);
+        r.gen_bools_packed(0, 0.5, out);
+        assert_eq!(out.to_owned(), ~[0, 0, 0]);
+
+        // With p = 1/2 each word is a single draw.
+        let mut a = XorShiftRng::new_seeded(1, 2, 3, 4);
+        let mut b = XorShiftRng::new_seeded(1, 2, 3, 4);
+        let mut out = [0u64, .. 2];
+        a.gen_bools_packed(128, 0.5, out);
+        assert_eq!(out.to_owned(), ~[b.gen::<u64>(), b.gen::<u64>()]);
+    }
+
+    #[test]
+    #[should_fail]
+    fn test_gen_bools_packed_short_out() {
+        rng().gen_bools_packed(129, 0.5, [0u64, .. 2]);
+    }
+
+    #[test]
+    fn test_gen_weights() {
+        let mut r = rng();
+        assert_eq!(r.gen_weights(1), ~[1.0]);
+
+        let mut heavy = 0;
+        for _ in range(0, 10000) {
+            let w = r.gen_weights(3);
+            assert_eq!(w.len(), 3);
+            assert!(w.iter().all(|&x| x >= 0.0));
+            let total = w.iter().fold(0.0, |a, &b| a + b);
+            assert!((total - 1.0).abs() < 1e-12);
+            if w[0] > 0.5 { heavy += 1 }
+        }
+        // Uniform on the simplex, w[0] > 1/2 has probability 1/4; normalized
+        // uniforms would give 1/6. The standard deviation is about 43.
+        assert!(heavy > 2300 && heavy < 2700);
+    }
+
+    #[test]
+    #[should_fail]
+    fn test_gen_weights_empty() {
+        rng().gen_weights(0);
+    }
+
+    #[test]
+    fn test_gen_weighted_bool_max() {
+        // A 1 in 2^32 or 2^64 chance, so effectively never true.
+        let mut r = rng();
+        for _ in range(0, 1000) {
+            assert!(!r.gen_weighted_bool(uint::max_value));
+        }
+
+        // It is true exactly when the 64-bit draw is a multiple of `n`.
+        // u64::max_value is a multiple of `n` on both 32- and 64-bit
+        // targets, so it lies outside the accepted zone and is redrawn.
+        assert!(PlaybackRng::new(~[0, 0]).gen_weighted_bool(uint::max_value));
+        assert!(!PlaybackRng::new(~[0, 1]).gen_weighted_bool(uint::max_value));
+        assert!(PlaybackRng::new(~[0xffff_ffff, 0xffff_ffff, 0, 0])
+                .gen_weighted_bool(uint::max_value));
+    }
+
+    #[test]
+    fn test_gen_backoff() {
+        let mut r = rng();
+        let mut last_total = 0;
+        for attempt in range(0u, 8) {
+            let mut total = 0;
+            for _ in range(0, 1000) {
+                let delay = r.gen_backoff(attempt, 10, 1000);
+                assert!(delay < 10 << attempt);
+                assert!(delay < 1000);
+                total += delay;
+            }
+            assert!(total > last_total);
+            last_total = total;
+        }
+
+        for &attempt in [63u, 64, 65, 1000, uint::max_value].iter() {
+            for _ in range(0, 100) {
+                assert!(r.gen_backoff(attempt, 10, 1000) < 1000);
+                assert!(r.gen_backoff(attempt, u64::max_value, 1000) < 1000);
+            }
+        }
+        assert_eq!(r.gen_backoff(3, 0, 1000), 0);
+        assert_eq!(r.gen_backoff(3, 10, 0), 0);
+    }
+
+    #[test]
+    fn test_gen_bivariate_normal() {
+        let mut r = rng();
+        for &rho in [-0.9, 0.0, 0.5].iter() {
+            let n = 10000;
+            let (mut sx, mut sy, mut sxx, mut syy, mut sxy) = (0.0, 0.0, 0.0, 0.0, 0.0);
+            for _ in range(0, n) {
+                let (x, y) = r.gen_bivariate_normal(rho);
+                sx += x; sy += y;
+                sxx += x * x; syy += y * y; sxy += x * y;
+            }
+            let n = n as f64;
+            let cov = sxy / n - (sx / n) * (sy / n);
+            let var_x = sxx / n - (sx / n) * (sx / n);
+            let var_y = syy / n - (sy / n) * (sy / n);
+            let corr = cov / (var_x * var_y).sqrt();
+            assert!((corr - rho).abs() < 0.05);
+        }
+
+        let (x, y) = r.gen_bivariate_normal(-1.0);
+        assert_eq!(x, -y);
+    }
+
+    #[test]
+    #[should_fail]
+    fn test_gen_bivariate_normal_invalid_rho() {
         let mut r = rng();
-        assert_eq!(r.gen_weighted_bool(0u), true);
-        assert_eq!(r.gen_weighted_bool(1u), true);
+        r.gen_bivariate_normal(1.5);
     }
 
     #[test]
@@ -954,6 +2764,81 @@ mod test {
         assert_eq!(r.gen_ascii_str(16u).len(), 16u);
     }
 
+    #[test]
+    fn test_gen_phone_na() {
+        let mut r = rng();
+        for _ in range(0, 1000) {
+            let phone = r.gen_phone_na();
+            let bytes = phone.as_bytes();
+            assert_eq!(bytes.len(), 14);
+            for (i, &b) in bytes.iter().enumerate() {
+                match i {
+                    0 => assert_eq!(b, '(' as u8),
+                    4 => assert_eq!(b, ')' as u8),
+                    5 => assert_eq!(b, ' ' as u8),
+                    9 => assert_eq!(b, '-' as u8),
+                    1 | 6 => assert!(b >= '2' as u8 && b <= '9' as u8),
+                    _ => assert!(b >= '0' as u8 && b <= '9' as u8)
+                }
+            }
+            assert!(phone.slice(2, 4) != "11" && phone.slice(7, 9) != "11");
+        }
+    }
+
+    #[test]
+    fn test_gen_user_agent() {
+        let mut r = rng();
+        let mut browsers = [0, ..3];
+        for _ in range(0, 300) {
+            let ua = r.gen_user_agent();
+            assert!(ua.starts_with("Mozilla/5.0 ("));
+            assert!(ua.is_ascii() && !ua.iter().any(|c| c.is_control()));
+            assert!(!ua.contains("  ") && !ua.ends_with(" "));
+
+            // The last product token names the browser and its version.
+            let product = ua.split_iter(' ').last().unwrap();
+            let (name, version) = match product.find('/') {
+                Some(i) => (product.slice_to(i), product.slice_from(i + 1)),
+                None => fail2!("no version in {}", ua)
+            };
+            assert!(!version.is_empty());
+            assert!(version.iter().all(|c| c.is_digit() || c == '.'));
+            if name == "Firefox" {
+                browsers[0] += 1;
+            } else if ua.contains("Chrome/") {
+                browsers[1] += 1;
+            } else {
+                assert!(ua.contains("Version/"));
+                assert_eq!(name, "Safari");
+                browsers[2] += 1;
+            }
+        }
+        assert!(browsers.iter().all(|&n| n > 0));
+    }
+
+    #[test]
+    fn test_gen_csv_field() {
+        fn is_special(c: char) -> bool {
+            c == ',' || c == '"' || c == '\r' || c == '\n'
+        }
+
+        let mut r = rng();
+        let mut quoted = 0;
+        for _ in range(0, 1000) {
+            let field = r.gen_csv_field();
+            if field.starts_with("\"") {
+                quoted += 1;
+                assert!(field.len() >= 2 && field.ends_with("\""));
+                let inner = field.slice(1, field.len() - 1);
+                assert!(!inner.replace("\"\"", "").contains_char('"'));
+                assert!(inner.iter().any(is_special));
+            } else {
+                assert!(!field.iter().any(is_special));
+            }
+        }
+        assert!(quoted > 0);
+    }
+
     #[test]
     fn test_gen_vec() {
         let mut r = rng();
@@ -979,6 +2864,21 @@ mod test {
         assert_eq!(r.choose_option(v), Some(&i));
     }
 
+    #[test]
+    fn test_choose_remove() {
+        let mut r = rng();
+        let mut v: ~[uint] = range(0u, 100).collect();
+        let mut seen = [false, ..100];
+        for i in range(0u, 100) {
+            let x = r.choose_remove(&mut v).unwrap();
+            assert!(!seen[x]);
+            seen[x] = true;
+            assert_eq!(v.len(), 99 - i);
+        }
+        assert!(seen.iter().all(|&b| b));
+        assert_eq!(r.choose_remove(&mut v), None);
+    }
+
     #[test]
     fn test_choose_weighted() {
         let mut r = rng();
@@ -1005,6 +2905,96 @@ mod test {
         assert!(v.is_none());
     }
 
+    #[test]
+    fn test_choose_weighted_ref() {
+        let mut r = rng();
+        let v = [
+            Weighted { weight: 0u, item: ~[1, 2] },
+            Weighted { weight: 3u, item: ~[3] },
+        ];
+        for _ in range(0, 100) {
+            let chosen = r.choose_weighted_ref(v).unwrap();
+            assert!(borrow::ref_eq(chosen, &v[1].item));
+        }
+        let empty: &[Weighted<~[int]>] = [];
+        assert!(r.choose_weighted_ref(empty).is_none());
+    }
+
+    #[test]
+    fn test_choose_weighted_float() {
+        let mut r = rng();
+        assert_eq!(r.choose_weighted_float([WeightedFloat { weight: 0.25, item: 42 }]), 42);
+        assert_eq!(r.choose_weighted_float([
+            WeightedFloat { weight: 0.0, item: 42 },
+            WeightedFloat { weight: 0.5, item: 43 },
+            WeightedFloat { weight: 0.0, item: 44 }
+        ]), 43);
+
+        let items = [WeightedFloat { weight: 0.1, item: 0u },
+                     WeightedFloat { weight: 0.6, item: 1u },
+                     WeightedFloat { weight: 0.3, item: 2u }];
+        let mut counts = [0, ..3];
+        for _ in range(0, 10000) {
+            counts[r.choose_weighted_float(items)] += 1;
+        }
+        // The standard deviations are at most 49.
+        assert!(counts[0] > 800 && counts[0] < 1200);
+        assert!(counts[1] > 5750 && counts[1] < 6250);
+        assert!(counts[2] > 2750 && counts[2] < 3250);
+
+        let empty: ~[WeightedFloat<int>] = ~[];
+        assert_eq!(r.choose_weighted_float_option(empty), None);
+        assert_eq!(r.choose_weighted_float_option([WeightedFloat { weight: 0.0, item: 1 }]),
+                   None);
+    }
+
+    #[test]
+    #[should_fail]
+    fn test_choose_weighted_float_negative() {
+        rng().choose_weighted_float_option([WeightedFloat { weight: 1.0, item: 1 },
+                                            WeightedFloat { weight: -0.5, item: 2 }]);
+    }
+
+    #[test]
+    fn test_sample_empirical() {
+        let mut r = rng();
+        let values = [1.0, 2.0];
+        let cdf = [0.3, 1.0];
+        let mut ones = 0;
+        for _ in range(0, 10000) {
+            let x = r.sample_empirical(values, cdf);
+            if x == 1.0 {
+                ones += 1;
+            } else {
+                assert_eq!(x, 2.0);
+            }
+        }
+        assert!(ones > 2700 && ones < 3300);
+
+        // Values with zero probability are never drawn.
+        let values = [1.0, 2.0, 3.0, 4.0];
+        let cdf = [0.0, 0.5, 0.5, 1.0];
+        for _ in range(0, 1000) {
+            let x = r.sample_empirical(values, cdf);
+            assert!(x == 2.0 || x == 4.0);
+        }
+        assert_eq!(r.sample_empirical([7.0], [1.0]), 7.0);
+    }
+
+    #[test]
+    #[should_fail]
+    fn test_sample_empirical_decreasing_cdf() {
+        let mut r = rng();
+        r.sample_empirical([1.0, 2.0, 3.0], [0.5, 0.4, 1.0]);
+    }
+
+    #[test]
+    #[should_fail]
+    fn test_sample_empirical_incomplete_cdf() {
+        let mut r = rng();
+        r.sample_empirical([1.0, 2.0], [0.3, 0.9]);
+    }
+
     #[test]
     fn test_weighted_vec() {
         let mut r = rng();
@@ -1017,6 +3007,420 @@ mod test {
         ]) == ~[2u, 1u, 1u]);
     }
 
+    #[test]
+    fn test_gen_matrix() {
+        let mut r = rng();
+        let m = r.gen_matrix(3, 5, &distributions::Normal::new(0.0, 1.0));
+        assert_eq!(m.len(), 3);
+        for row in m.iter() {
+            assert_eq!(row.len(), 5);
+        }
+        assert!(r.gen_matrix(0, 5, &distributions::Normal::new(0.0, 1.0)).is_empty());
+    }
+
+    #[test]
+    fn test_gen_orthogonal() {
+        let mut r = rng();
+        let n = 6;
+        let q = r.gen_orthogonal(n);
+        assert_eq!(q.len(), n);
+        // Q Q^T must be the identity.
+        for i in range(0, n) {
+            assert_eq!(q[i].len(), n);
+            for j in range(0, n) {
+                let mut dot = 0.0;
+                for k in range(0, n) {
+                    dot += q[i][k] * q[j][k];
+                }
+                let expected = if i == j { 1.0 } else { 0.0 };
+                assert!((dot - expected).abs() < 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn test_gen_sparse() {
+        let mut r = rng();
+        let entries = r.gen_sparse(10000, 0.1);
+        assert!(entries.len() > 900 && entries.len() < 1100);
+        for (k, &(i, x)) in entries.iter().enumerate() {
+            assert!(i < 10000);
+            // Strictly increasing indices are in particular distinct.
+            if k > 0 {
+                let (prev, _) = entries[k - 1];
+                assert!(prev < i);
+            }
+            assert!(x >= 0.0 && x < 1.0);
+        }
+
+        assert!(r.gen_sparse(100, 0.0).is_empty());
+        assert_eq!(r.gen_sparse(100, 1.0).len(), 100);
+        assert!(r.gen_sparse(0, 0.5).is_empty());
+    }
+
+    #[test]
+    fn test_gen_latency_ms() {
+        let mut r = rng();
+        let samples: ~[u64] = range(0, 10000).map(|_| r.gen_latency_ms(100.0, 1.0)).collect();
+        // About half the samples fall below the median...
+        let below = samples.iter().count(|&x| x < 100);
+        assert!(below > 4500 && below < 5500);
+        // ...and with sigma 1 about 5% exceed five times the median.
+        assert!(samples.iter().any(|&x| x > 500));
+
+        assert_eq!(r.gen_latency_ms(250.0, 0.0), 250);
+    }
+
+    #[test]
+    fn test_fill_bytes() {
+        let mut r = rng();
+        let mut v = [0u8, .. 100];
+        r.fill_bytes(v);
+        // All zeros has probability 2^-800.
+        assert!(v.iter().any(|&b| b != 0));
+
+        let mut a = XorShiftRng::new_seeded(1, 2, 3, 4);
+        let mut b = XorShiftRng::new_seeded(1, 2, 3, 4);
+        let mut v = [0u8, .. 8];
+        a.fill_bytes(v);
+        let (x, y) = (b.next(), b.next());
+        assert_eq!(v.to_owned(),
+                   ~[x as u8, (x >> 8) as u8, (x >> 16) as u8, (x >> 24) as u8,
+                     y as u8, (y >> 8) as u8, (y >> 16) as u8, (y >> 24) as u8]);
+    }
+
+    #[test]
+    fn test_rng_clone() {
+        use rand::chacha::ChaChaRng;
+
+        // Draw from `a` for a while, then check a clone follows the same
+        // stream as the original from there on.
+        fn check<R: Rng + Clone>(mut a: R) {
+            for _ in range(0, 37) {
+                a.next();
+            }
+            let mut b = a.clone();
+            for _ in range(0, 100) {
+                assert_eq!(a.next(), b.next());
+            }
+        }
+        check(XorShiftRng::new());
+        check(IsaacRng::new());
+        check(ChaChaRng::new());
+        check(PortableRng::new());
+    }
+
+    #[test]
+    fn test_fill_bytes_empty() {
+        use rand::chacha::ChaChaRng;
+
+        // Filling an empty slice must not draw from the generator.
+        fn check<R: Rng>(mut a: R, mut b: R) {
+            a.fill_bytes([]);
+            assert_eq!(a.next(), b.next());
+        }
+        check(XorShiftRng::new_seeded(1, 2, 3, 4), XorShiftRng::new_seeded(1, 2, 3, 4));
+        check(IsaacRng::new_seeded([1, 2, 3]), IsaacRng::new_seeded([1, 2, 3]));
+        check(ChaChaRng::new_unseeded(), ChaChaRng::new_unseeded());
+        check(PortableRng::new_seeded([1, 2, 3]), PortableRng::new_seeded([1, 2, 3]));
+    }
+
+    #[test]
+    fn test_fill_bytes_unaligned() {
+        for len in range(0u, 18) {
+            let mut a = XorShiftRng::new_seeded(1, 2, 3, 4);
+            let mut b = XorShiftRng::new_seeded(1, 2, 3, 4);
+            let mut v = vec::from_elem(len, 0u8);
+            a.fill_bytes(v);
+
+            let mut expected = ~[];
+            while expected.len() < len {
+                let word = b.next();
+                for shift in range_step(0u32, 32, 8) {
+                    expected.push((word >> shift) as u8);
+                }
+            }
+            expected.truncate(len);
+            assert_eq!(v, expected);
+
+            // no partial word is left behind for the next draw
+            assert_eq!(a.next(), b.next());
+        }
+    }
+
+    #[test]
+    fn test_gen_tz_offset_seconds() {
+        let mut r = rng();
+        let mut seen_min = false;
+        let mut seen_max = false;
+        for _ in range(0, 10000) {
+            let offset = r.gen_tz_offset_seconds();
+            assert_eq!(offset % 900, 0);
+            assert!(offset >= -43200 && offset <= 50400);
+            seen_min = seen_min || offset == -43200;
+            seen_max = seen_max || offset == 50400;
+        }
+        // Each end has probability 1/105 per draw.
+        assert!(seen_min && seen_max);
+    }
+
+    #[test]
+    fn test_gen_ident() {
+        let mut r = rng();
+        for _ in range(0, 1000) {
+            let ident = r.gen_ident(8);
+            assert!(ident.len() >= 1 && ident.len() <= 8);
+            let first = ident.char_at(0);
+            assert!(first.is_alphabetic() || first == '_');
+            for c in ident.iter() {
+                assert!(c.is_alphanumeric() || c == '_');
+            }
+            assert!(ident != ~"_");
+        }
+
+        // Short identifiers hit keywords like `fn`, `if` and `do` often, so
+        // they would turn up here if they were not filtered out.
+        for _ in range(0, 10000) {
+            let ident = r.gen_ident(2);
+            for kw in ["as", "do", "fn", "if", "in", "be"].iter() {
+                assert!(ident.as_slice() != *kw);
+            }
+        }
+    }
+
+    #[test]
+    fn test_gen_mnemonic() {
+        assert_eq!(MNEMONIC_WORDS.len(), 256);
+        for pair in MNEMONIC_WORDS.window_iter(2) {
+            assert!(pair[0] < pair[1]);
+        }
+
+        let mut r = rng();
+        for &n in [1u, 12, 24].iter() {
+            let phrase = r.gen_mnemonic(n);
+            let words: ~[&str] = phrase.split_iter(' ').collect();
+            assert_eq!(words.len(), n);
+            for word in words.iter() {
+                assert!(MNEMONIC_WORDS.iter().any(|w| *w == *word));
+            }
+        }
+        assert_eq!(r.gen_mnemonic(0), ~"");
+    }
+
+    #[test]
+    fn test_gen_token() {
+        let mut r = rng();
+        for byte_len in range(0u, 20) {
+            let token = r.gen_token(byte_len);
+            assert_eq!(token.len(), (byte_len * 4 + 2) / 3);
+            for c in token.iter() {
+                assert!(c.is_alphanumeric() || c == '-' || c == '_');
+            }
+        }
+    }
+
+    #[test]
+    fn test_gen_point_in_bbox() {
+        let mut r = rng();
+        for _ in range(0, 1000) {
+            let (lat, lon) = r.gen_point_in_bbox(40.0, -75.0, 41.0, -73.0);
+            assert!(lat >= 40.0 && lat <= 41.0);
+            assert!(lon >= -75.0 && lon <= -73.0);
+        }
+
+        // Crossing the antimeridian, both sides must be hit.
+        let mut east = false;
+        let mut west = false;
+        for _ in range(0, 1000) {
+            let (lat, lon) = r.gen_point_in_bbox(-21.0, 170.0, -12.0, -170.0);
+            assert!(lat >= -21.0 && lat <= -12.0);
+            assert!(lon >= 170.0 || lon <= -170.0);
+            assert!(lon >= -180.0 && lon <= 180.0);
+            if lon > 0.0 { east = true; } else { west = true; }
+        }
+        assert!(east && west);
+    }
+
+    #[test]
+    #[should_fail]
+    fn test_gen_point_in_bbox_bad_latitude() {
+        let mut r = rng();
+        r.gen_point_in_bbox(-91.0, 0.0, 0.0, 10.0);
+    }
+
+    #[test]
+    fn test_count_successes() {
+        let mut r = rng();
+        // The standard error of the mean of 100 draws is about 145.
+        let n = 10_000_000u64;
+        let mut sum = 0u64;
+        for _ in range(0, 100) {
+            sum += r.count_successes(n, 0.3);
+        }
+        let mean = sum as f64 / 100.0;
+        assert!(num::abs(mean - 3_000_000.0) < 1000.0);
+
+        // Looping over the trials would never finish.
+        let huge = u64::max_value / 2;
+        for _ in range(0, 1000) {
+            assert!(r.count_successes(huge, 0.25) <= huge);
+        }
+
+        assert_eq!(r.count_successes(0, 0.5), 0);
+        assert_eq!(r.count_successes(1000, 0.0), 0);
+        assert_eq!(r.count_successes(1000, 1.0), 1000);
+    }
+
+    #[test]
+    fn test_seedable_rng() {
+        let mut isaac: IsaacRng = SeedableRng::from_seed(&[1u32, 2, 3, 4]);
+        let first = isaac.gen_vec::<u32>(300);
+        isaac.reseed(&[1u32, 2, 3, 4]);
+        assert_eq!(isaac.gen_vec::<u32>(300), first);
+        isaac.reseed(&[1u32, 2, 3, 5]);
+        assert!(isaac.gen_vec::<u32>(300) != first);
+
+        let mut xorshift: XorShiftRng = SeedableRng::from_seed([1u32, 2, 3, 4]);
+        let first = xorshift.gen_vec::<u32>(100);
+        xorshift.reseed([1u32, 2, 3, 4]);
+        assert_eq!(xorshift.gen_vec::<u32>(100), first);
+        let mut other = XorShiftRng::new_seeded(1, 2, 3, 4);
+        assert_eq!(other.gen_vec::<u32>(100), first);
+
+        let seed = bytes!("replay");
+        let mut portable: PortableRng = SeedableRng::from_seed(seed);
+        let first = portable.gen_vec::<u32>(100);
+        portable.reseed(seed);
+        assert_eq!(portable.gen_vec::<u32>(100), first);
+        assert_eq!(PortableRng::new_seeded(seed).gen_vec::<u32>(100), first);
+    }
+
+    #[test]
+    fn test_xorshift_jump() {
+        let mut a = XorShiftRng::new_seeded(1, 2, 3, 4);
+        a.jump();
+        assert_eq!(vec::from_fn(4, |_| a.next()),
+                   ~[0x3ef51fd7, 0xe719f9b3, 0x28e31ba6, 0xd89d83de]);
+
+        // Jumping commutes with stepping.
+        let mut b = XorShiftRng::new_seeded(5, 6, 7, 8);
+        let mut c = b.clone();
+        b.jump();
+        b.next();
+        c.next();
+        c.jump();
+        for _ in range(0, 100) {
+            assert_eq!(b.next(), c.next());
+        }
+    }
+
+    #[test]
+    #[should_fail]
+    fn test_xorshift_zero_seed() {
+        let _rng: XorShiftRng = SeedableRng::from_seed([0u32, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_gen_char() {
+        let mut r = rng();
+        let mut astral = 0u;
+        for _ in range(0, 10000) {
+            let c = r.gen::<char>() as u32;
+            assert!(c <= 0x10FFFF);
+            assert!(c < 0xD800 || c > 0xDFFF);
+            if c > 0xFFFF {
+                astral += 1;
+            }
+        }
+        // About 94% of scalar values are outside the Basic Multilingual Plane.
+        assert!(astral > 9000);
+    }
+
+    #[test]
+    fn test_gen_walk() {
+        let mut r = rng();
+        let walk = r.gen_walk(-7, 1000, 3);
+        assert_eq!(walk.len(), 1001);
+        assert_eq!(walk[0], -7);
+        let mut moved = false;
+        for pair in walk.window_iter(2) {
+            let step = pair[1] - pair[0];
+            assert!(step >= -3 && step <= 3);
+            moved = moved || step != 0;
+        }
+        assert!(moved);
+
+        assert_eq!(r.gen_walk(5, 0, 3), ~[5]);
+        assert_eq!(r.gen_walk(5, 3, 0), ~[5, 5, 5, 5]);
+    }
+
+    #[test]
+    fn test_gen_maze() {
+        let mut r = rng();
+        for &(width, height) in [(1u, 1u), (1, 7), (5, 1), (12, 9)].iter() {
+            let maze = r.gen_maze(width, height);
+            let (rows, cols) = (2 * height + 1, 2 * width + 1);
+            assert_eq!(maze.len(), rows);
+            assert!(maze.iter().all(|row| row.len() == cols));
+            assert!(maze[0].iter().all(|&wall| wall));
+            assert!(maze[rows - 1].iter().all(|&wall| wall));
+            assert!(maze.iter().all(|row| row[0] && row[cols - 1]));
+
+            // Flood fill from the first cell: a perfect maze is connected
+            // and has exactly one passage fewer than it has cells.
+            let mut seen = vec::from_elem(rows, vec::from_elem(cols, false));
+            let mut stack = ~[(1u, 1u)];
+            seen[1][1] = true;
+            let mut reached = 0u;
+            while !stack.is_empty() {
+                let (row, col) = stack.pop();
+                reached += 1;
+                for &(nr, nc) in [(row - 1, col), (row + 1, col),
+                                  (row, col - 1), (row, col + 1)].iter() {
+                    if !maze[nr][nc] && !seen[nr][nc] {
+                        seen[nr][nc] = true;
+                        stack.push((nr, nc));
+                    }
+                }
+            }
+            let open = maze.iter().map(|row| row.iter().count(|&wall| !wall))
+                .fold(0u, |a, b| a + b);
+            assert_eq!(reached, open);
+            assert_eq!(open, 2 * width * height - 1);
+            for y in range(0u, height) {
+                for x in range(0u, width) {
+                    assert!(seen[2 * y + 1][2 * x + 1]);
+                }
+            }
+        }
+
+        let mut a = XorShiftRng::new_seeded(1, 2, 3, 4);
+        let mut b = XorShiftRng::new_seeded(1, 2, 3, 4);
+        assert_eq!(a.gen_maze(10, 10), b.gen_maze(10, 10));
+    }
+
+    #[test]
+    #[should_fail]
+    fn test_gen_maze_empty() {
+        rng().gen_maze(0, 3);
+    }
+
+    #[test]
+    fn test_gen_random_tree() {
+        let mut r = rng();
+        for n in range(0u, 50) {
+            let parents = r.gen_random_tree(n);
+            assert_eq!(parents.len(), n);
+            for (i, &parent) in parents.iter().enumerate() {
+                if i == 0 {
+                    assert_eq!(parent, 0);
+                } else {
+                    assert!(parent < i);
+                }
+            }
+        }
+    }
+
     #[test]
     fn test_shuffle() {
         let mut r = rng();
@@ -1025,6 +3429,56 @@ mod test {
         assert_eq!(r.shuffle(~[1, 1, 1]), ~[1, 1, 1]);
     }
 
+    #[test]
+    fn test_gen_iter() {
+        let mut r = rng();
+        let v = r.gen_iter::<u8>().take(16).collect::<~[u8]>();
+        assert_eq!(v.len(), 16);
+        let evens = r.gen_iter::<uint>().filter(|x| x % 2 == 0).take(10).collect::<~[uint]>();
+        assert_eq!(evens.len(), 10);
+        assert!(evens.iter().all(|x| x % 2 == 0));
+
+        // The values are the ones `gen` would have drawn.
+        let mut a = XorShiftRng::new_seeded(1, 2, 3, 4);
+        let mut b = XorShiftRng::new_seeded(1, 2, 3, 4);
+        let from_iter = a.gen_iter::<f64>().take(5).collect::<~[f64]>();
+        assert_eq!(from_iter, b.gen_vec::<f64>(5));
+    }
+
+    #[test]
+    fn test_shuffle_indices() {
+        let mut a = XorShiftRng::new_seeded(1, 2, 3, 4);
+        let mut b = XorShiftRng::new_seeded(1, 2, 3, 4);
+        let values = ~["a", "b", "c", "d", "e", "f", "g", "h"];
+
+        let indices = a.shuffle_indices(values.len());
+        let mut shuffled = values.clone();
+        b.shuffle_mut(shuffled);
+        assert_eq!(indices.map(|&i| values[i]), shuffled);
+
+        let mut identity = vec::from_fn(values.len(), |i| i);
+        a.shuffle_mut(identity);
+        assert_eq!(b.shuffle_indices(values.len()), identity);
+
+        assert_eq!(a.shuffle_indices(0), ~[]);
+        assert_eq!(a.shuffle_indices(1), ~[0]);
+    }
+
+    #[test]
+    fn test_draw_iter() {
+        let mut r = rng();
+        let values = [1, 2, 3, 4, 5, 6, 7, 8];
+        let drawn = r.draw_iter(values).collect::<~[int]>();
+        assert_eq!(drawn.len(), values.len());
+        for v in values.iter() {
+            assert_eq!(drawn.iter().count(|x| *x == *v), 1);
+        }
+
+        assert_eq!(r.draw_iter(values).take(3).collect::<~[int]>().len(), 3);
+        let empty: &[int] = [];
+        assert!(r.draw_iter(empty).next().is_none());
+    }
+
     #[test]
     fn test_task_rng() {
         let mut r = task_rng();
@@ -1045,6 +3499,34 @@ mod test {
                      (f32, (f64, (f64,)))) = random();
     }
 
+    #[test]
+    fn test_rand_hashmap() {
+        let mut r = rng();
+        for _ in range(0, 100) {
+            let m: HashMap<u64, int> = r.gen();
+            assert!(m.len() < 16);
+        }
+    }
+
+    #[test]
+    fn test_rand_vec_lengths() {
+        let mut r = rng();
+        let mut saw_empty = false;
+        let mut saw_nonempty = false;
+        for _ in range(0, 1000) {
+            let v: ~[Option<u8>] = r.gen();
+            assert!(v.len() < 16);
+            if v.is_empty() { saw_empty = true } else { saw_nonempty = true }
+
+            let v: @[bool] = r.gen();
+            assert!(v.len() < 16);
+        }
+        assert!(saw_empty && saw_nonempty);
+
+        let vv: ~[~[(u8, i8)]] = r.gen();
+        assert!(vv.iter().all(|v| v.len() < 16));
+    }
+
     #[test]
     fn test_sample() {
         let MIN_VAL = 1;
@@ -1062,6 +3544,37 @@ mod test {
             **e >= MIN_VAL && **e <= MAX_VAL
         }));
     }
+
+    #[test]
+    fn test_choose_multiple() {
+        let mut r = rng();
+        let deck = range(0, 52).to_owned_vec();
+        let empty: ~[int] = ~[];
+        assert_eq!(r.choose_multiple(empty, 3), ~[]);
+        assert_eq!(r.choose_multiple(deck, 0), ~[]);
+
+        for _ in range(0, 100) {
+            let hand = r.choose_multiple(deck, 5);
+            assert_eq!(hand.len(), 5);
+            for (i, card) in hand.iter().enumerate() {
+                assert!(*card >= 0 && *card < 52);
+                assert!(!hand.slice_from(i + 1).contains(card));
+            }
+        }
+
+        // asking for more than there is returns a permutation of everything
+        let all = r.choose_multiple([1, 2, 3], 10);
+        assert_eq!(all.len(), 3);
+        assert!(all.contains(&1) && all.contains(&2) && all.contains(&3));
+
+        // the order of the result is random too
+        let mut first = HashMap::new();
+        for _ in range(0, 1000) {
+            let v = r.choose_multiple([1, 2, 3], 3);
+            first.insert(v[0], ());
+        }
+        assert_eq!(first.len(), 3);
+    }
 }
 
 #[cfg(test)]